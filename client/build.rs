@@ -1,3 +1,16 @@
+use std::process::Command;
+
 fn main() {
     sp1_build::build_program("../program");
+
+    // Expose the git version to /health
+    let git_version = Command::new("git")
+        .args(["describe", "--always", "--dirty"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_VERSION={}", git_version.trim());
+    println!("cargo:rerun-if-changed=../.git/HEAD");
 }
@@ -1,15 +1,28 @@
 use std::net::SocketAddr;
 
 use axum::{
-    routing::{get, post},
+    http::HeaderValue,
+    middleware,
+    routing::{delete, get, post},
     Router,
 };
 use sp1_sdk::include_elf;
+use tokio::signal;
 use tower::ServiceBuilder;
-use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing::{error, info, warn};
+use tracing_subscriber::EnvFilter;
 
-use crate::server::handlers::{generate_bitcoin_proof, health_check};
+use crate::server::handlers::{
+    cancel_proof_job, estimate_cycles, generate_batch_proof, generate_bitcoin_proof,
+    generate_bitcoin_proof_by_txid, get_vkey, health_check, proof_job_status, public_values,
+    stream_bitcoin_proof, submit_proof_job, verify_offchain,
+};
+use crate::server::limits::retry_after_on_unavailable;
+use crate::server::openapi::openapi_spec;
+use crate::server::prover::{Prover, ProverMode};
+use crate::server::request_id::request_id_middleware;
+use crate::server::state::{AppState, ProgramElf};
 
 pub mod server;
 
@@ -25,25 +38,65 @@ async fn main() {
     dotenv::dotenv().ok();
 
     // Initialize structured logging
-    tracing_subscriber::fmt()
-        .with_target(false)
-        .with_thread_ids(true)
-        .with_thread_names(true)
-        .pretty()
-        .init();
+    init_tracing();
+
+    // Pick the prover backend; a bad PROVER_MODE stops startup
+    let prover = match ProverMode::from_env().and_then(ProverMode::client) {
+        Ok(prover) => prover,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    info!("Prover mode: {}", prover.mode().as_str());
+
+    // The single-transaction program: embedded, or from PROGRAM_ELF_PATH
+    let program_elf = match ProgramElf::from_env() {
+        Ok(elf) => elf,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    info!(
+        "Program ELF: {} ({} bytes)",
+        program_elf.source(),
+        program_elf.bytes().len()
+    );
+
+    // Run prover setup in the background; /health reports ready once it finishes,
+    // and a failed setup (e.g. a corrupt ELF) stops the server instead of leaving it unready
+    let state = AppState::new(prover).with_program_elf(program_elf);
+    let setup_state = state.clone();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = setup_state.run_setup() {
+            error!("Prover setup failed: {}", e);
+            std::process::exit(1);
+        }
+    });
 
     // Build the HTTP router with CORS support
     let app = Router::new()
         .route("/health", get(health_check))
         .route("/prove", post(generate_bitcoin_proof))
+        .route("/prove/by-txid", post(generate_bitcoin_proof_by_txid))
+        .route("/prove/batch", post(generate_batch_proof))
+        .route("/prove/stream", get(stream_bitcoin_proof))
+        .route("/prove/async", post(submit_proof_job))
+        .route("/prove/status", get(proof_job_status))
+        .route("/prove/:job_id", delete(cancel_proof_job))
+        .route("/verify-offchain", post(verify_offchain))
+        .route("/estimate", post(estimate_cycles))
+        .route("/public-values", post(public_values))
+        .route("/vkey", get(get_vkey))
+        .route("/openapi.json", get(openapi_spec))
         .layer(
-            ServiceBuilder::new().layer(
-                CorsLayer::new()
-                    .allow_origin(Any)
-                    .allow_methods(Any)
-                    .allow_headers(Any),
-            ),
-        );
+            ServiceBuilder::new()
+                .layer(cors_layer())
+                .layer(middleware::from_fn(request_id_middleware))
+                .layer(middleware::map_response(retry_after_on_unavailable)),
+        )
+        .with_state(state);
 
     // Configure server address
     let addr = SocketAddr::from(([0, 0, 0, 0], 4455));
@@ -52,5 +105,158 @@ async fn main() {
     info!("Server starting on http://0.0.0.0:4455");
     // Start the server
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .unwrap();
+
+    info!("Server stopped");
+}
+
+/// Log output format, selected by `LOG_FORMAT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogFormat {
+    Pretty,
+    Json,
+}
+
+/// Parse `LOG_FORMAT`: `pretty` (the default, for local development) or `json`
+fn parse_log_format(value: Option<&str>) -> Result<LogFormat, String> {
+    match value.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+        None | Some("") | Some("pretty") => Ok(LogFormat::Pretty),
+        Some("json") => Ok(LogFormat::Json),
+        Some(other) => Err(format!(
+            "unsupported LOG_FORMAT {}: expected pretty or json",
+            other
+        )),
+    }
+}
+
+/// Install the global subscriber in the `LOG_FORMAT` format,
+/// filtered by `RUST_LOG` (info when unset)
+fn init_tracing() {
+    let format =
+        parse_log_format(std::env::var("LOG_FORMAT").ok().as_deref()).expect("invalid LOG_FORMAT");
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false)
+        .with_thread_ids(true)
+        .with_thread_names(true);
+    match format {
+        LogFormat::Pretty => builder.pretty().init(),
+        LogFormat::Json => builder.json().init(),
+    }
+}
+
+/// CORS layer: explicit allow-list from `ALLOWED_ORIGINS` (comma-separated),
+/// or any origin when unset for local development
+fn cors_layer() -> CorsLayer {
+    let cors = CorsLayer::new().allow_methods(Any).allow_headers(Any);
+    match std::env::var("ALLOWED_ORIGINS") {
+        Ok(value) => {
+            let origins = parse_allowed_origins(&value).expect("invalid ALLOWED_ORIGINS");
+            info!("CORS restricted to {} origin(s)", origins.len());
+            cors.allow_origin(AllowOrigin::list(origins))
+        }
+        Err(_) => {
+            warn!("ALLOWED_ORIGINS not set, allowing any origin");
+            cors.allow_origin(Any)
+        }
+    }
+}
+
+/// Parse a comma-separated origin list, trimming whitespace and skipping empty entries
+fn parse_allowed_origins(value: &str) -> Result<Vec<HeaderValue>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(|origin| {
+            HeaderValue::from_str(origin).map_err(|e| format!("invalid origin {}: {}", origin, e))
+        })
+        .collect()
+}
+
+/// Resolves on Ctrl+C or (on unix) SIGTERM so in-flight requests can finish before exit
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    wait_for_shutdown(async {
+        tokio::select! {
+            _ = ctrl_c => {},
+            _ = terminate => {},
+        }
+    })
+    .await;
+}
+
+/// Waits for `signal`, then logs that the server is draining
+async fn wait_for_shutdown(signal: impl std::future::Future<Output = ()>) {
+    signal.await;
+    info!("Shutdown signal received, waiting for in-flight requests to finish");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_wait_for_shutdown_resolves_on_signal() {
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let shutdown = tokio::spawn(wait_for_shutdown(async {
+            rx.await.ok();
+        }));
+
+        // Still waiting while no signal has arrived
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!shutdown.is_finished());
+
+        tx.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), shutdown)
+            .await
+            .expect("shutdown future should resolve after the signal")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!(parse_log_format(None), Ok(LogFormat::Pretty));
+        assert_eq!(parse_log_format(Some("")), Ok(LogFormat::Pretty));
+        assert_eq!(parse_log_format(Some("pretty")), Ok(LogFormat::Pretty));
+        assert_eq!(parse_log_format(Some(" JSON ")), Ok(LogFormat::Json));
+        assert!(parse_log_format(Some("logfmt")).is_err());
+    }
+
+    #[test]
+    fn test_parse_allowed_origins() {
+        let origins =
+            parse_allowed_origins(" https://app.example.com, http://localhost:3000 ,,").unwrap();
+        assert_eq!(
+            origins,
+            vec![
+                HeaderValue::from_static("https://app.example.com"),
+                HeaderValue::from_static("http://localhost:3000"),
+            ]
+        );
+
+        assert!(parse_allowed_origins("").unwrap().is_empty());
+        assert!(parse_allowed_origins("https://ok.example, bad\norigin").is_err());
+    }
 }
@@ -0,0 +1,222 @@
+use fibonacci_lib::from_esplora_merkle_proof;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+/// Default public Esplora instances per network
+const MAINNET_ESPLORA_URL: &str = "https://blockstream.info/api";
+const TESTNET_ESPLORA_URL: &str = "https://blockstream.info/testnet/api";
+const SIGNET_ESPLORA_URL: &str = "https://mempool.space/signet/api";
+
+/// Proof inputs assembled from an Esplora explorer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchedProofInputs {
+    /// Raw transaction hex
+    pub tx: String,
+    /// Merkle siblings (explorer display hex)
+    pub merkle: Vec<String>,
+    /// Position in the merkle tree
+    pub position: usize,
+    /// Block header hex
+    pub block_header: String,
+}
+
+/// Errors returned while fetching proof inputs from an explorer
+#[derive(Debug)]
+pub enum FetchError {
+    /// Explorer answered 404 for the requested resource
+    NotFound(String),
+    /// Explorer could not be reached or returned a non-404 error status
+    Network(String),
+    /// Explorer answered with an unexpected body
+    InvalidResponse(String),
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            FetchError::Network(msg) => write!(f, "Explorer unavailable: {}", msg),
+            FetchError::InvalidResponse(msg) => write!(f, "Invalid explorer response: {}", msg),
+        }
+    }
+}
+
+/// `/tx/{txid}/status` response
+#[derive(Deserialize, Debug)]
+struct EsploraTxStatus {
+    confirmed: bool,
+    block_hash: Option<String>,
+}
+
+/// Resolve the Esplora base URL for a network
+/// `ESPLORA_URL_MAINNET`, `ESPLORA_URL_TESTNET` and `ESPLORA_URL_SIGNET` override the
+/// defaults, so an override never serves one network's data for another
+pub fn esplora_base_url(network: &str) -> Result<String, String> {
+    let (default_url, override_var) = match network {
+        "mainnet" | "bitcoin" => (MAINNET_ESPLORA_URL, "ESPLORA_URL_MAINNET"),
+        "testnet" => (TESTNET_ESPLORA_URL, "ESPLORA_URL_TESTNET"),
+        "signet" => (SIGNET_ESPLORA_URL, "ESPLORA_URL_SIGNET"),
+        other => return Err(format!("unsupported network: {}", other)),
+    };
+    let url = std::env::var(override_var).unwrap_or_else(|_| default_url.to_string());
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+/// Minimal Esplora REST client
+pub struct EsploraClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl EsploraClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Fetch raw tx, merkle proof and block header for a confirmed txid
+    pub async fn fetch_proof_inputs(&self, txid: &str) -> Result<FetchedProofInputs, FetchError> {
+        let tx = self.get_text(&format!("/tx/{}/hex", txid)).await?;
+
+        let status: EsploraTxStatus = self.get_json(&format!("/tx/{}/status", txid)).await?;
+        let block_hash = match (status.confirmed, status.block_hash) {
+            (true, Some(hash)) => hash,
+            _ => return Err(FetchError::NotFound(format!("tx {} is unconfirmed", txid))),
+        };
+
+        let proof: serde_json::Value = self.get_json(&format!("/tx/{}/merkle-proof", txid)).await?;
+        let (merkle, position) =
+            from_esplora_merkle_proof(&proof).map_err(FetchError::InvalidResponse)?;
+        let block_header = self
+            .get_text(&format!("/block/{}/header", block_hash))
+            .await?;
+
+        Ok(FetchedProofInputs {
+            tx: tx.trim().to_string(),
+            merkle,
+            position,
+            block_header: block_header.trim().to_string(),
+        })
+    }
+
+    async fn get(&self, path: &str) -> Result<reqwest::Response, FetchError> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| FetchError::Network(e.to_string()))?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Err(FetchError::NotFound(url)),
+            status if !status.is_success() => {
+                Err(FetchError::Network(format!("{} returned {}", url, status)))
+            }
+            _ => Ok(response),
+        }
+    }
+
+    async fn get_text(&self, path: &str) -> Result<String, FetchError> {
+        self.get(path)
+            .await?
+            .text()
+            .await
+            .map_err(|e| FetchError::InvalidResponse(e.to_string()))
+    }
+
+    async fn get_json<T: for<'de> Deserialize<'de>>(&self, path: &str) -> Result<T, FetchError> {
+        self.get(path)
+            .await?
+            .json::<T>()
+            .await
+            .map_err(|e| FetchError::InvalidResponse(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode as AxumStatus, routing::get, Json, Router};
+    use fibonacci_lib::fixtures::{
+        merkle_siblings, BLOCK_HASH, BLOCK_HEADER, POSITION, TXID, TX_HEX,
+    };
+    use serde_json::json;
+
+    /// Serve captured Esplora responses for the block-363348 transaction
+    async fn spawn_mock_esplora() -> String {
+        let app = Router::new()
+            .route(&format!("/tx/{}/hex", TXID), get(|| async { TX_HEX }))
+            .route(
+                &format!("/tx/{}/status", TXID),
+                get(|| async {
+                    Json(json!({
+                        "confirmed": true,
+                        "block_height": 363348,
+                        "block_hash": BLOCK_HASH,
+                        "block_time": 1435754650
+                    }))
+                }),
+            )
+            .route(
+                &format!("/tx/{}/merkle-proof", TXID),
+                get(|| async {
+                    Json(json!({
+                        "block_height": 363348,
+                        "merkle": merkle_siblings(),
+                        "pos": 1465
+                    }))
+                }),
+            )
+            .route(
+                &format!("/block/{}/header", BLOCK_HASH),
+                get(|| async { BLOCK_HEADER }),
+            )
+            .fallback(|| async { (AxumStatus::NOT_FOUND, "Transaction not found") });
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_proof_inputs_from_mock_esplora() {
+        let base_url = spawn_mock_esplora().await;
+        let client = EsploraClient::new(&base_url);
+
+        let inputs = client.fetch_proof_inputs(TXID).await.unwrap();
+        assert_eq!(inputs.tx, TX_HEX);
+        assert_eq!(inputs.merkle, merkle_siblings());
+        assert_eq!(inputs.position, POSITION);
+        assert_eq!(inputs.block_header, BLOCK_HEADER);
+
+        // Unknown txid maps to NotFound
+        let missing = "00".repeat(32);
+        assert!(matches!(
+            client.fetch_proof_inputs(&missing).await,
+            Err(FetchError::NotFound(_))
+        ));
+
+        // Unreachable explorer maps to Network
+        let offline = EsploraClient::new("http://127.0.0.1:1");
+        assert!(matches!(
+            offline.fetch_proof_inputs(TXID).await,
+            Err(FetchError::Network(_))
+        ));
+    }
+
+    #[test]
+    fn test_esplora_base_url_override_is_per_network() {
+        std::env::set_var("ESPLORA_URL_SIGNET", "http://127.0.0.1:3002/");
+        assert_eq!(esplora_base_url("signet").unwrap(), "http://127.0.0.1:3002");
+        // Other networks keep their own default
+        assert_eq!(esplora_base_url("mainnet").unwrap(), MAINNET_ESPLORA_URL);
+        assert_eq!(esplora_base_url("testnet").unwrap(), TESTNET_ESPLORA_URL);
+        std::env::remove_var("ESPLORA_URL_SIGNET");
+
+        assert!(esplora_base_url("regtest").is_err());
+    }
+}
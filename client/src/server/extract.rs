@@ -0,0 +1,94 @@
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::StatusCode,
+    Json,
+};
+use serde::de::DeserializeOwned;
+use tracing::warn;
+
+use crate::server::handlers::{error_response, ProofError, ProofResponse};
+
+/// `Json` extractor whose rejection is a `ProofResponse` with an `INVALID_JSON` code
+/// instead of Axum's plain-text body
+pub struct JsonBody<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, Json<ProofResponse>);
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(request, state).await {
+            Ok(Json(value)) => Ok(JsonBody(value)),
+            Err(rejection) => Err(json_rejection(rejection)),
+        }
+    }
+}
+
+/// Keep Axum's status (400 syntax, 415 content type, 422 shape) and its message,
+/// which names the offending field
+fn json_rejection(rejection: JsonRejection) -> (StatusCode, Json<ProofResponse>) {
+    warn!("Rejected request body: {}", rejection.body_text());
+    error_response(
+        rejection.status(),
+        ProofError::InvalidJson(rejection.body_text()),
+        std::time::Instant::now(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::{handlers::generate_bitcoin_proof, prover::MockProver, state::AppState};
+    use axum::{routing::post, Router};
+
+    #[tokio::test]
+    async fn test_malformed_body_returns_structured_error() {
+        let app = Router::new()
+            .route("/prove", post(generate_bitcoin_proof))
+            .with_state(AppState::new(MockProver::new(Vec::new())));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = reqwest::Client::new();
+        let url = format!("http://{}/prove", addr);
+
+        // Missing the required block header
+        let response = client
+            .post(&url)
+            .json(&serde_json::json!({
+                "tx": "00",
+                "tx_hash": "00",
+                "merkle": [],
+                "position": 0,
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["error_code"], "INVALID_JSON");
+        let error = body["error"].as_str().unwrap();
+        assert!(
+            error.contains("block_header"),
+            "unexpected error: {}",
+            error
+        );
+
+        // Syntax errors keep their 400 status
+        let response = client
+            .post(&url)
+            .header("content-type", "application/json")
+            .body("{\"tx\": ")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error_code"], "INVALID_JSON");
+    }
+}
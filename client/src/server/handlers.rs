@@ -1,47 +1,534 @@
-use axum::{http::StatusCode, response::Json};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json,
+    },
+};
 
+use alloy_sol_types::SolType;
+use fibonacci_lib::{
+    build_verification_report, check_tree_depth, decompress_merkle_proof_hex,
+    diagnose_merkle_proof, is_valid_address, prepare_proof_input, sats_to_btc_string,
+    target_outpoint, total_op_return_bytes, verify_batch, verify_inputs_confirmed,
+    verify_op_return_commitment, verify_tx_in_block_and_outputs, BatchProofInput,
+    BatchPublicValuesStruct, BatchResult, FundingProof, Network, ProofInput, ProofSystem,
+    PublicValuesStruct, VerificationReport, VerificationResult,
+};
 use serde::{Deserialize, Serialize};
-use sp1_sdk::{include_elf, ProverClient, SP1Stdin};
-use tracing::{info, warn};
+use sp1_sdk::{include_elf, SP1ProofMode, SP1Stdin};
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{error, info, warn, Instrument};
+use utoipa::{IntoParams, ToSchema};
 
-use crate::TARGET_ADDRESS;
+use crate::server::esplora::{esplora_base_url, EsploraClient, FetchError, FetchedProofInputs};
+use crate::server::extract::JsonBody;
+use crate::server::jobs::JobResponse;
+use crate::server::prover::{verify_after_prove, Prover};
+use crate::server::request_id::current_request_id;
+use crate::server::state::{AppState, ProgramKeys, ProofStats, ProverSetup};
 
 /// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
 pub const BITCOIN_PROOF_ELF: &[u8] = include_elf!("fibonacci-program");
 
+/// The batch program: several transactions from one block in a single proof
+pub const BATCH_PROOF_ELF: &[u8] = include_elf!("batch");
+
 /// Request structure for Bitcoin transaction proof generation
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct ProofRequest {
     /// Raw Bitcoin transaction hex string
     pub tx: String,
     /// Expected Bitcoin transaction ID (hex string)
     pub tx_hash: String,
     /// Merkle siblings (array of hex strings)
+    #[serde(default)]
     pub merkle: Vec<String>,
     /// Position in the merkle tree
+    #[serde(default)]
     pub position: usize,
+    /// Compact merkle proof (`compress_merkle_proof_hex`), instead of `merkle` and `position`
+    #[serde(default)]
+    pub proof_blob: Option<String>,
     /// Block header (hex string)
     pub block_header: String,
+    /// Also commit the total OP_RETURN payload bytes as a public value
+    #[serde(default)]
+    pub commit_op_return_bytes: bool,
+    /// Merkle proofs for the funding transactions of every input (chained-proof mode)
+    #[serde(default)]
+    #[schema(value_type = Vec<Object>)]
+    pub funding_proofs: Vec<FundingProof>,
+    /// Proof system: compressed (default), plonk or groth16
+    #[serde(default)]
+    #[schema(value_type = String)]
+    pub system: ProofSystem,
+    /// Pin the block: the proof fails unless the header hashes to this (display hex)
+    #[serde(default)]
+    pub expected_block_hash: Option<String>,
+    /// Reject coinbase transactions (minted subsidy rather than a payment)
+    #[serde(default)]
+    pub reject_coinbase: bool,
+    /// Prove a total of 0 instead of failing when no output pays the target
+    #[serde(default)]
+    pub allow_no_match: bool,
+    /// Also commit whether an OP_RETURN output carries exactly this 32-byte value (hex,
+    /// in script byte order)
+    #[serde(default)]
+    pub expected_op_return: Option<String>,
+    /// Pre-check the merkle proof and explain common sibling mistakes
+    /// (wrong byte order, root-first order) instead of a generic failure
+    #[serde(default)]
+    pub check_siblings: bool,
+}
+
+impl ProofRequest {
+    /// Merkle siblings and position, from `proof_blob` when given
+    /// Proofs deeper than `MAX_TREE_DEPTH` are rejected
+    fn merkle_proof(&self) -> Result<(Vec<String>, usize), ProofError> {
+        let (merkle, position) = match &self.proof_blob {
+            Some(_) if !self.merkle.is_empty() => {
+                return Err(ProofError::ValidationFailed(
+                    "give either proof_blob or merkle, not both".to_string(),
+                ))
+            }
+            Some(blob) => decompress_merkle_proof_hex(blob)
+                .map_err(|e| ProofError::ValidationFailed(format!("invalid proof_blob: {}", e)))?,
+            None => (self.merkle.clone(), self.position),
+        };
+        check_tree_depth(merkle.len()).map_err(ProofError::InvalidMerkleSiblings)?;
+        validate_merkle_siblings(&self.tx_hash, &merkle, position)?;
+        Ok((merkle, position))
+    }
+
+    /// The program input for this request, checked and normalized by `prepare_proof_input`
+    fn proof_input(&self) -> Result<ProofInput, ProofError> {
+        let (merkle, position) = self.merkle_proof()?;
+        prepare_proof_input(
+            &self.tx,
+            &self.tx_hash,
+            &merkle,
+            position,
+            &self.block_header,
+        )
+        .map_err(ProofError::ValidationFailed)
+    }
+
+    /// The modes this request asks for, summing outputs to `target_address`
+    fn modes(&self, target_address: &str, expected_op_return: Option<[u8; 32]>) -> ProofModes {
+        ProofModes {
+            target_address: target_address.to_string(),
+            commit_op_return_bytes: self.commit_op_return_bytes,
+            inputs_checked: !self.funding_proofs.is_empty(),
+            block_hash_pinned: self.expected_block_hash.is_some(),
+            reject_coinbase: self.reject_coinbase,
+            allow_no_match: self.allow_no_match,
+            expected_op_return: expected_op_return.map(hex::encode),
+        }
+    }
+}
+
+/// Canonical encoding of everything that determines a request's proof, for cache keys
+/// Fields are written in a fixed order as length-prefixed values, hex is lowercased,
+/// and options that only affect error reporting (`check_siblings`) are left out.
+/// A `proof_blob` is expanded to its merkle list and position, so both forms of the same
+/// proof share a key, and siblings are sorted: the proven statement (tx, block, target)
+/// does not depend on their order
+pub fn canonical_request_bytes(request: &ProofRequest) -> Vec<u8> {
+    let decoded_blob = request
+        .proof_blob
+        .as_deref()
+        .map(|blob| decompress_merkle_proof_hex(blob).map_err(|_| blob));
+    let (merkle, position, undecodable_blob) = match decoded_blob {
+        Some(Ok((merkle, position))) => (merkle, position, None),
+        Some(Err(blob)) => (request.merkle.clone(), request.position, Some(blob)),
+        None => (request.merkle.clone(), request.position, None),
+    };
+    let mut merkle: Vec<String> = merkle.iter().map(|s| normalize_hex(s)).collect();
+    merkle.sort();
+
+    let mut out = Vec::new();
+    push_hex(&mut out, &request.tx);
+    push_hex(&mut out, &request.tx_hash);
+    push_len(&mut out, merkle.len());
+    for sibling in &merkle {
+        push_hex(&mut out, sibling);
+    }
+    push_len(&mut out, position);
+    // A blob that does not decode is kept as-is; the request fails validation anyway
+    push_optional_hex(&mut out, undecodable_blob);
+    push_hex(&mut out, &request.block_header);
+    out.push(request.commit_op_return_bytes as u8);
+    push_len(&mut out, request.funding_proofs.len());
+    for funding in &request.funding_proofs {
+        push_hex(&mut out, &funding.tx_hex);
+        push_len(&mut out, funding.merkle_siblings.len());
+        for sibling in &funding.merkle_siblings {
+            push_hex(&mut out, sibling);
+        }
+        push_len(&mut out, funding.pos);
+        push_hex(&mut out, &funding.block_header);
+    }
+    out.push(request.system as u8);
+    push_optional_hex(&mut out, request.expected_block_hash.as_deref());
+    out.push(request.reject_coinbase as u8);
+    out.push(request.allow_no_match as u8);
+    push_optional_hex(&mut out, request.expected_op_return.as_deref());
+    out
+}
+
+fn push_len(out: &mut Vec<u8>, len: usize) {
+    out.extend_from_slice(&(len as u64).to_le_bytes());
+}
+
+/// Hex trimmed, without `0x` and lowercased so equal values compare equally
+fn normalize_hex(hex: &str) -> String {
+    let hex = hex.trim();
+    hex.strip_prefix("0x").unwrap_or(hex).to_ascii_lowercase()
+}
+
+/// Hex field, normalized so equal values encode equally
+fn push_hex(out: &mut Vec<u8>, hex: &str) {
+    let hex = normalize_hex(hex);
+    push_len(out, hex.len());
+    out.extend_from_slice(hex.as_bytes());
+}
+
+fn push_optional_hex(out: &mut Vec<u8>, hex: Option<&str>) {
+    match hex {
+        Some(hex) => {
+            out.push(1);
+            push_hex(out, hex);
+        }
+        None => out.push(0),
+    }
+}
+
+/// Reject sibling lists that cannot belong to a real proof: the transaction as its own
+/// left-hand sibling, or the same hash at two adjacent levels
+/// (A right-hand sibling equal to the transaction is Bitcoin's duplicated last leaf)
+fn validate_merkle_siblings(
+    tx_hash: &str,
+    merkle: &[String],
+    position: usize,
+) -> Result<(), ProofError> {
+    let normalize = |hash: &str| {
+        let hash = hash.trim();
+        hash.strip_prefix("0x").unwrap_or(hash).to_ascii_lowercase()
+    };
+    let siblings: Vec<String> = merkle.iter().map(|s| normalize(s)).collect();
+    if position % 2 == 1 && siblings.first() == Some(&normalize(tx_hash)) {
+        return Err(ProofError::InvalidMerkleSiblings(
+            "sibling 0 is the transaction's own hash".to_string(),
+        ));
+    }
+    if let Some(level) = siblings.windows(2).position(|pair| pair[0] == pair[1]) {
+        return Err(ProofError::InvalidMerkleSiblings(format!(
+            "siblings {} and {} are identical",
+            level,
+            level + 1
+        )));
+    }
+    Ok(())
+}
+
+/// Request structure for proving a transaction by txid, fetching inputs from an explorer
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct ProveByTxidRequest {
+    /// Bitcoin transaction ID (explorer display hex)
+    pub txid: String,
+    /// Address the proof sums outputs to
+    pub target_address: String,
+    /// Bitcoin network: mainnet, testnet or signet
+    #[serde(default = "default_network")]
+    pub network: String,
+    /// Also commit the total OP_RETURN payload bytes as a public value
+    #[serde(default)]
+    pub commit_op_return_bytes: bool,
+    /// Proof system: compressed (default), plonk or groth16
+    #[serde(default)]
+    #[schema(value_type = String)]
+    pub system: ProofSystem,
+    /// Pin the block: the proof fails unless the header hashes to this (display hex)
+    #[serde(default)]
+    pub expected_block_hash: Option<String>,
+    /// Reject coinbase transactions (minted subsidy rather than a payment)
+    #[serde(default)]
+    pub reject_coinbase: bool,
+    /// Prove a total of 0 instead of failing when no output pays the target
+    #[serde(default)]
+    pub allow_no_match: bool,
+    /// Also commit whether an OP_RETURN output carries exactly this 32-byte value (hex,
+    /// in script byte order)
+    #[serde(default)]
+    pub expected_op_return: Option<String>,
+}
+
+impl ProveByTxidRequest {
+    /// The direct proof request for this txid, from the inputs fetched for it
+    fn proof_request(&self, inputs: FetchedProofInputs) -> ProofRequest {
+        ProofRequest {
+            tx: inputs.tx,
+            tx_hash: self.txid.clone(),
+            merkle: inputs.merkle,
+            position: inputs.position,
+            proof_blob: None,
+            block_header: inputs.block_header,
+            commit_op_return_bytes: self.commit_op_return_bytes,
+            funding_proofs: Vec::new(),
+            system: self.system,
+            expected_block_hash: self.expected_block_hash.clone(),
+            reject_coinbase: self.reject_coinbase,
+            allow_no_match: self.allow_no_match,
+            expected_op_return: self.expected_op_return.clone(),
+            check_siblings: false,
+        }
+    }
+}
+
+fn default_network() -> String {
+    "mainnet".to_string()
 }
 
 /// Response structure for proof generation
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 pub struct ProofResponse {
     /// Success status
     pub success: bool,
     /// Error message if any
     pub error: Option<String>,
+    /// Machine-readable error code (e.g. `INVALID_JSON`) if any
+    pub error_code: Option<String>,
     pub public_values: Option<Vec<u8>>,
     pub proof_bytes: Option<Vec<u8>>,
     /// Execution time in milliseconds
     pub execution_time_ms: Option<u64>,
+    /// Native verification result the proof attests
+    #[schema(value_type = Option<Object>)]
+    pub verification: Option<VerificationResult>,
+    /// Native per-claim results a batch proof attests
+    #[schema(value_type = Option<Object>)]
+    pub batch: Option<BatchResult>,
+    /// Correlation id of the request, also found in the server logs
+    pub request_id: Option<String>,
+}
+
+/// `ProofResponse` fields, in serialization order
+const PROOF_RESPONSE_FIELDS: [&str; 9] = [
+    "success",
+    "error",
+    "error_code",
+    "public_values",
+    "proof_bytes",
+    "execution_time_ms",
+    "verification",
+    "batch",
+    "request_id",
+];
+
+/// Query parameters selecting which `ProofResponse` fields to return
+#[derive(Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FieldsQuery {
+    /// Comma-separated field names, e.g. `success,proof`; all fields when absent
+    pub fields: Option<String>,
+}
+
+/// Resolve requested field names, accepting `proof` as shorthand for `proof_bytes`
+fn parse_fields(fields: Option<&str>) -> Result<Option<Vec<&'static str>>, String> {
+    let Some(fields) = fields else {
+        return Ok(None);
+    };
+    let mut selected = Vec::new();
+    for name in fields.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let name = if name == "proof" { "proof_bytes" } else { name };
+        let field = PROOF_RESPONSE_FIELDS
+            .iter()
+            .find(|f| **f == name)
+            .ok_or_else(|| format!("unknown response field: {}", name))?;
+        selected.push(*field);
+    }
+    Ok(Some(selected))
+}
+
+/// Serialize a response keeping only the selected fields (all when `None`)
+fn filter_fields(response: &ProofResponse, fields: Option<&[&str]>) -> serde_json::Value {
+    let mut value = serde_json::to_value(response).expect("ProofResponse serializes");
+    if let (Some(fields), Some(map)) = (fields, value.as_object_mut()) {
+        map.retain(|key, _| fields.contains(&key.as_str()));
+    }
+    value
 }
 
 /// Health check response
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct HealthResponse {
+    /// `ready` once prover setup finished, `starting` before
     pub status: String,
     pub version: String,
+    /// `git describe` of the build, `unknown` outside a git checkout
+    pub git_version: String,
+    /// Size of the loaded program ELF in bytes
+    pub elf_size: usize,
+    /// Prover backend: `local` or `network`
+    pub prover_mode: String,
+    /// Duration of the most recent proof attempt, absent before the first
+    pub last_proof_ms: Option<u64>,
+    /// Proof attempts since startup, successful or not
+    pub total_proofs: u64,
+    /// Fraction of proof attempts that succeeded, 0 before the first
+    pub success_rate: f64,
+}
+
+/// Verifying key response
+#[derive(Serialize, Debug, ToSchema)]
+pub struct VkeyResponse {
+    /// 32-byte verifying key digest, `0x`-prefixed hex, for on-chain verifiers
+    pub vkey: String,
+}
+
+/// Cycle estimate for a proof request
+#[derive(Serialize, Debug, ToSchema)]
+pub struct EstimateResponse {
+    /// Whether the program ran to completion (any failed check aborts execution)
+    pub valid: bool,
+    /// Total RISC-V cycles, when execution completed
+    pub cycles: Option<u64>,
+    /// Why execution aborted
+    pub error: Option<String>,
+    /// Wall-clock execution time in milliseconds
+    pub execution_time_ms: u64,
+}
+
+/// Public values a proof request would commit, from executing the program without proving
+#[derive(Serialize, Debug, ToSchema)]
+pub struct PublicValuesResponse {
+    /// Whether the program ran to completion (any failed check aborts execution)
+    pub valid: bool,
+    /// Committed public values (hex), byte for byte what a proof would carry
+    pub public_values: Option<String>,
+    /// `public_values` decoded field by field
+    pub decoded: Option<DecodedPublicValues>,
+    /// Why execution aborted
+    pub error: Option<String>,
+    /// Wall-clock execution time in milliseconds
+    pub execution_time_ms: u64,
+}
+
+/// Fields of the single-transaction program's public values
+#[derive(Serialize, Debug, PartialEq, Eq, ToSchema)]
+pub struct DecodedPublicValues {
+    pub block_hash: String,
+    pub total_amount: u64,
+    /// Txid of the proven transaction (explorer display hex; committed in internal order)
+    pub txid: String,
+    /// First output paying the target, or u32::MAX when none does
+    pub output_index: u32,
+    pub is_coinbase: bool,
+    pub target_address: String,
+    /// Present when `commit_op_return_bytes` was requested
+    pub op_return_bytes: Option<u32>,
+    /// Whether funding proofs were checked (chained-proof mode)
+    pub inputs_checked: bool,
+    /// False unless funding proofs were given and every input is confirmed
+    pub all_inputs_confirmed: bool,
+    /// Block hash of each funding proof's header, in proof order
+    pub funding_block_hashes: Vec<String>,
+    /// Whether `expected_block_hash` pinned the proof to `block_hash`
+    pub block_hash_pinned: bool,
+    pub coinbase_rejected: bool,
+    pub no_match_allowed: bool,
+    /// The `expected_op_return` checked for (hex), when given
+    pub expected_op_return: Option<String>,
+    /// False unless `expected_op_return` was given and an OP_RETURN output carries it
+    pub op_return_anchored: bool,
+}
+
+impl DecodedPublicValues {
+    /// The modes the committed flags say the proof was made with
+    fn modes(&self) -> ProofModes {
+        ProofModes {
+            target_address: self.target_address.clone(),
+            commit_op_return_bytes: self.op_return_bytes.is_some(),
+            inputs_checked: self.inputs_checked,
+            block_hash_pinned: self.block_hash_pinned,
+            reject_coinbase: self.coinbase_rejected,
+            allow_no_match: self.no_match_allowed,
+            expected_op_return: self.expected_op_return.clone(),
+        }
+    }
+}
+
+/// What a single-transaction proof request asks the program to check, as committed
+/// in the public values' mode flags
+#[derive(Debug, PartialEq, Eq)]
+struct ProofModes {
+    target_address: String,
+    commit_op_return_bytes: bool,
+    inputs_checked: bool,
+    block_hash_pinned: bool,
+    reject_coinbase: bool,
+    allow_no_match: bool,
+    /// Lowercase hex
+    expected_op_return: Option<String>,
+}
+
+/// What a single-transaction proof must commit beyond `VerificationResult`, computed
+/// natively the way the program computes it
+#[derive(Debug, PartialEq, Eq)]
+struct ExpectedValues {
+    modes: ProofModes,
+    output_index: u32,
+    /// Present when `commit_op_return_bytes` was requested
+    op_return_bytes: Option<u32>,
+    all_inputs_confirmed: bool,
+    funding_block_hashes: Vec<String>,
+    op_return_anchored: bool,
+}
+
+impl ExpectedValues {
+    /// Compute the values for `tx_hex`, which `verification` verified under `modes`
+    fn compute(
+        tx_hex: &str,
+        verification: &VerificationResult,
+        modes: ProofModes,
+        funding_proofs: &[FundingProof],
+        expected_op_return: Option<[u8; 32]>,
+    ) -> Result<Self, ProofError> {
+        let output_index = match verification.matched_outputs {
+            0 => u32::MAX,
+            _ => {
+                target_outpoint(tx_hex, &modes.target_address)
+                    .map_err(ProofError::ValidationFailed)?
+                    .1
+            }
+        };
+        let op_return_bytes = match modes.commit_op_return_bytes {
+            true => Some(total_op_return_bytes(tx_hex).map_err(ProofError::ValidationFailed)?),
+            false => None,
+        };
+        let inputs = verify_inputs_confirmed(tx_hex, funding_proofs)
+            .map_err(|e| ProofError::ValidationFailed(format!("invalid funding proof: {}", e)))?;
+        let op_return_anchored = match expected_op_return {
+            Some(expected) => verify_op_return_commitment(tx_hex, expected)
+                .map_err(ProofError::ValidationFailed)?,
+            None => false,
+        };
+        Ok(Self {
+            modes,
+            output_index,
+            op_return_bytes,
+            all_inputs_confirmed: !funding_proofs.is_empty() && inputs.all_confirmed,
+            funding_block_hashes: inputs.funding_block_hashes,
+            op_return_anchored,
+        })
+    }
 }
 
 /// Error types for better error handling
@@ -53,6 +540,36 @@ pub enum ProofError {
     ProofGenerationFailed(String),
     ValidationFailed(String),
     DecodeError(String),
+    ExplorerNotFound(String),
+    ExplorerUnavailable(String),
+    Timeout(String),
+    ProverNotReady(String),
+    ProverBusy(String),
+    InvalidJson(String),
+    PublicValuesMismatch(String),
+    JobNotFound(String),
+}
+
+impl ProofError {
+    /// Stable machine-readable code for the error kind
+    pub fn code(&self) -> &'static str {
+        match self {
+            ProofError::InvalidHex(_) => "INVALID_HEX",
+            ProofError::InvalidMerkleSiblings(_) => "INVALID_MERKLE_SIBLINGS",
+            ProofError::InvalidMerkleRoot(_) => "INVALID_MERKLE_ROOT",
+            ProofError::ProofGenerationFailed(_) => "PROOF_GENERATION_FAILED",
+            ProofError::ValidationFailed(_) => "VALIDATION_FAILED",
+            ProofError::DecodeError(_) => "DECODE_ERROR",
+            ProofError::ExplorerNotFound(_) => "EXPLORER_NOT_FOUND",
+            ProofError::ExplorerUnavailable(_) => "EXPLORER_UNAVAILABLE",
+            ProofError::Timeout(_) => "TIMEOUT",
+            ProofError::ProverNotReady(_) => "PROVER_NOT_READY",
+            ProofError::ProverBusy(_) => "PROVER_BUSY",
+            ProofError::InvalidJson(_) => "INVALID_JSON",
+            ProofError::PublicValuesMismatch(_) => "PUBLIC_VALUES_MISMATCH",
+            ProofError::JobNotFound(_) => "JOB_NOT_FOUND",
+        }
+    }
 }
 
 impl std::fmt::Display for ProofError {
@@ -64,105 +581,2545 @@ impl std::fmt::Display for ProofError {
             ProofError::ProofGenerationFailed(msg) => write!(f, "Proof generation failed: {}", msg),
             ProofError::ValidationFailed(msg) => write!(f, "Validation failed: {}", msg),
             ProofError::DecodeError(msg) => write!(f, "Decode error: {}", msg),
+            ProofError::ExplorerNotFound(msg) => write!(f, "Explorer returned not found: {}", msg),
+            ProofError::ExplorerUnavailable(msg) => write!(f, "Explorer unavailable: {}", msg),
+            ProofError::Timeout(msg) => write!(f, "Proof generation timed out: {}", msg),
+            ProofError::ProverNotReady(msg) => write!(f, "Prover not ready: {}", msg),
+            ProofError::ProverBusy(msg) => write!(f, "Prover busy: {}", msg),
+            ProofError::InvalidJson(msg) => write!(f, "Invalid JSON: {}", msg),
+            ProofError::PublicValuesMismatch(msg) => {
+                write!(f, "Committed public values mismatch: {}", msg)
+            }
+            ProofError::JobNotFound(msg) => write!(f, "Job not found: {}", msg),
         }
     }
 }
 
-/// Health check endpoint for monitoring service status
-pub async fn health_check() -> Json<HealthResponse> {
-    Json(HealthResponse {
-        status: "healthy".to_string(),
-        version: env!("CARGO_PKG_VERSION").to_string(),
-    })
+/// Health check endpoint: 200 once prover setup finished, 503 while it is still running
+#[utoipa::path(
+    get,
+    path = "/health",
+    responses(
+        (status = 200, description = "Prover ready", body = HealthResponse),
+        (status = 503, description = "Prover setup still running", body = HealthResponse),
+    )
+)]
+pub async fn health_check<P: Prover>(
+    State(state): State<AppState<P>>,
+) -> (StatusCode, Json<HealthResponse>) {
+    let (status, label) = match state.prover() {
+        Some(_) => (StatusCode::OK, "ready"),
+        None => (StatusCode::SERVICE_UNAVAILABLE, "starting"),
+    };
+    (
+        status,
+        Json(HealthResponse {
+            status: label.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_version: option_env!("GIT_VERSION").unwrap_or("unknown").to_string(),
+            elf_size: state.program_elf().bytes().len(),
+            prover_mode: state.prover_mode().as_str().to_string(),
+            last_proof_ms: state.proof_stats().last_proof_ms(),
+            total_proofs: state.proof_stats().total(),
+            success_rate: state.proof_stats().success_rate(),
+        }),
+    )
+}
+
+/// Verifying key of the single-transaction program, from the cached setup
+#[utoipa::path(
+    get,
+    path = "/vkey",
+    responses(
+        (status = 200, description = "Verifying key digest", body = VkeyResponse),
+        (status = 503, description = "Prover starting", body = ProofResponse),
+    )
+)]
+pub async fn get_vkey<P: Prover>(
+    State(state): State<AppState<P>>,
+) -> Result<Json<VkeyResponse>, (StatusCode, Json<ProofResponse>)> {
+    let setup = state
+        .prover()
+        .ok_or_else(|| prover_not_ready(std::time::Instant::now()))?;
+    Ok(Json(VkeyResponse {
+        vkey: setup.single.vkey_digest(),
+    }))
+}
+
+/// Run the full verification natively and return the report without proving
+#[utoipa::path(
+    post,
+    path = "/verify-offchain",
+    request_body = ProofRequest,
+    responses(
+        (status = 200, description = "Native verification report", body = Object),
+        (status = 400, description = "Invalid request", body = ProofResponse),
+    )
+)]
+pub async fn verify_offchain<P: Prover>(
+    State(state): State<AppState<P>>,
+    JsonBody(request): JsonBody<ProofRequest>,
+) -> Result<Json<VerificationReport>, (StatusCode, Json<ProofResponse>)> {
+    let start_time = std::time::Instant::now();
+
+    let (merkle, position) = request
+        .merkle_proof()
+        .inspect_err(|e| warn!("{}", e))
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e, start_time))?;
+    let report = build_verification_report(
+        &request.tx,
+        &request.tx_hash,
+        &merkle,
+        position,
+        &request.block_header,
+        state.target_address(),
+    )
+    .map_err(|e| {
+        warn!("Verification report failed: {}", e);
+        error_response(
+            StatusCode::BAD_REQUEST,
+            ProofError::ValidationFailed(e),
+            start_time,
+        )
+    })?;
+    info!("Verification report generated");
+    Ok(Json(report))
 }
 
 /// Generate proof for Bitcoin transaction verification
-pub async fn generate_bitcoin_proof(
-    Json(request): Json<ProofRequest>,
-) -> Result<Json<ProofResponse>, StatusCode> {
+#[utoipa::path(
+    post,
+    path = "/prove",
+    params(FieldsQuery),
+    request_body = ProofRequest,
+    responses(
+        (status = 200, description = "Proof generated", body = ProofResponse),
+        (status = 400, description = "Invalid request", body = ProofResponse),
+        (status = 500, description = "Proof committed values that disagree with native verification", body = ProofResponse),
+        (status = 503, description = "Prover starting or busy", body = ProofResponse),
+        (status = 504, description = "Proof generation timed out", body = ProofResponse),
+    )
+)]
+pub async fn generate_bitcoin_proof<P: Prover>(
+    State(state): State<AppState<P>>,
+    Query(query): Query<FieldsQuery>,
+    JsonBody(request): JsonBody<ProofRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ProofResponse>)> {
+    let start_time = std::time::Instant::now();
+
+    let fields = parse_fields(query.fields.as_deref()).map_err(|e| {
+        error_response(
+            StatusCode::BAD_REQUEST,
+            ProofError::ValidationFailed(e),
+            start_time,
+        )
+    })?;
+
+    let (stdin, verification, expected) = prepare_proof(&request, state.target_address())
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e, start_time))?;
+
+    let response = prove_single(
+        &state,
+        request.system,
+        stdin,
+        &request.tx_hash,
+        verification,
+        &expected,
+        start_time,
+    )
+    .await?;
+    Ok(Json(filter_fields(&response, fields.as_deref())))
+}
+
+/// Query of `GET /prove/status`
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct JobQuery {
+    pub job_id: String,
+}
+
+/// Start proving in the background and return a job id to poll with `GET /prove/status`
+/// The request is verified natively first, so invalid input is still a 400 here
+#[utoipa::path(
+    post,
+    path = "/prove/async",
+    request_body = ProofRequest,
+    responses(
+        (status = 202, description = "Proof job started", body = JobResponse),
+        (status = 400, description = "Invalid request", body = ProofResponse),
+    )
+)]
+pub async fn submit_proof_job<P: Prover>(
+    State(state): State<AppState<P>>,
+    JsonBody(request): JsonBody<ProofRequest>,
+) -> Result<(StatusCode, Json<JobResponse>), (StatusCode, Json<ProofResponse>)> {
+    let start_time = std::time::Instant::now();
+
+    let (stdin, verification, expected) = prepare_proof(&request, state.target_address())
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e, start_time))?;
+
+    let jobs = state.jobs().clone();
+    let job_id = jobs.create();
+    let task_state = state.clone();
+    let task_jobs = jobs.clone();
+    let task_job_id = job_id.clone();
+    let task = tokio::spawn(async move {
+        let response = match prove_single(
+            &task_state,
+            request.system,
+            stdin,
+            &request.tx_hash,
+            verification,
+            &expected,
+            start_time,
+        )
+        .await
+        {
+            Ok(response) => response,
+            Err((_, Json(response))) => response,
+        };
+        let result = serde_json::to_value(&response).expect("ProofResponse serializes");
+        task_jobs.finish(&task_job_id, response.success, result);
+    });
+    jobs.attach(&job_id, task.abort_handle());
+    info!("Proof job {} started", job_id);
+
+    let job = jobs.get(&job_id).expect("job was just created");
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+/// State of a background proof job, with its proof response once finished
+#[utoipa::path(
+    get,
+    path = "/prove/status",
+    params(JobQuery),
+    responses(
+        (status = 200, description = "Job state", body = JobResponse),
+        (status = 404, description = "Unknown job id", body = ProofResponse),
+    )
+)]
+pub async fn proof_job_status<P: Prover>(
+    State(state): State<AppState<P>>,
+    Query(query): Query<JobQuery>,
+) -> Result<Json<JobResponse>, (StatusCode, Json<ProofResponse>)> {
+    state
+        .jobs()
+        .get(&query.job_id)
+        .map(Json)
+        .ok_or_else(|| job_not_found(&query.job_id))
+}
+
+/// Cancel a pending proof job, freeing its prover slot; a finished job is left as is
+/// Either way the job's state afterwards is returned
+#[utoipa::path(
+    delete,
+    path = "/prove/{job_id}",
+    params(("job_id" = String, Path, description = "Id returned by `/prove/async`")),
+    responses(
+        (status = 200, description = "Job state after cancelling", body = JobResponse),
+        (status = 404, description = "Unknown job id", body = ProofResponse),
+    )
+)]
+pub async fn cancel_proof_job<P: Prover>(
+    State(state): State<AppState<P>>,
+    Path(job_id): Path<String>,
+) -> Result<Json<JobResponse>, (StatusCode, Json<ProofResponse>)> {
+    let job = state
+        .jobs()
+        .cancel(&job_id)
+        .ok_or_else(|| job_not_found(&job_id))?;
+    info!("Proof job {} is {:?}", job_id, job.status);
+    Ok(Json(job))
+}
+
+fn job_not_found(job_id: &str) -> (StatusCode, Json<ProofResponse>) {
+    error_response(
+        StatusCode::NOT_FOUND,
+        ProofError::JobNotFound(job_id.to_string()),
+        std::time::Instant::now(),
+    )
+}
+
+/// Generate proof for a transaction by txid, fetching all inputs from an Esplora explorer
+#[utoipa::path(
+    post,
+    path = "/prove/by-txid",
+    params(FieldsQuery),
+    request_body = ProveByTxidRequest,
+    responses(
+        (status = 200, description = "Proof generated", body = ProofResponse),
+        (status = 400, description = "Invalid request", body = ProofResponse),
+        (status = 404, description = "Transaction not found by the explorer", body = ProofResponse),
+        (status = 500, description = "Proof committed values that disagree with native verification", body = ProofResponse),
+        (status = 502, description = "Explorer unreachable or returned bad data", body = ProofResponse),
+        (status = 503, description = "Prover starting or busy", body = ProofResponse),
+        (status = 504, description = "Proof generation timed out", body = ProofResponse),
+    )
+)]
+pub async fn generate_bitcoin_proof_by_txid<P: Prover>(
+    State(state): State<AppState<P>>,
+    Query(query): Query<FieldsQuery>,
+    JsonBody(request): JsonBody<ProveByTxidRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ProofResponse>)> {
+    let start_time = std::time::Instant::now();
+
+    let fields = parse_fields(query.fields.as_deref()).map_err(|e| {
+        error_response(
+            StatusCode::BAD_REQUEST,
+            ProofError::ValidationFailed(e),
+            start_time,
+        )
+    })?;
+
+    info!("Fetching proof inputs for {}", request.txid);
+
+    let base_url = esplora_base_url(&request.network).map_err(|e| {
+        error_response(
+            StatusCode::BAD_REQUEST,
+            ProofError::ValidationFailed(e),
+            start_time,
+        )
+    })?;
+
+    validate_txid(&request.txid)
+        .inspect_err(|e| warn!("{}", e))
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e, start_time))?;
+    validate_target_address(&request.target_address, &request.network)
+        .inspect_err(|e| warn!("{}", e))
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e, start_time))?;
+    parse_op_return_commitment(request.expected_op_return.as_deref())
+        .inspect_err(|e| warn!("{}", e))
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e, start_time))?;
+
+    let inputs = EsploraClient::new(&base_url)
+        .fetch_proof_inputs(&request.txid)
+        .await
+        .map_err(|e| {
+            warn!("Fetching proof inputs failed: {}", e);
+            let (status, error) = match e {
+                FetchError::NotFound(msg) => {
+                    (StatusCode::NOT_FOUND, ProofError::ExplorerNotFound(msg))
+                }
+                FetchError::Network(msg) | FetchError::InvalidResponse(msg) => (
+                    StatusCode::BAD_GATEWAY,
+                    ProofError::ExplorerUnavailable(msg),
+                ),
+            };
+            error_response(status, error, start_time)
+        })?;
+
+    let (stdin, verification, expected) =
+        prepare_proof(&request.proof_request(inputs), &request.target_address)
+            .map_err(|e| error_response(StatusCode::BAD_REQUEST, e, start_time))?;
+
+    let response = prove_single(
+        &state,
+        request.system,
+        stdin,
+        &request.txid,
+        verification,
+        &expected,
+        start_time,
+    )
+    .await?;
+    Ok(Json(filter_fields(&response, fields.as_deref())))
+}
+
+/// Request structure for batch proof generation
+#[derive(Deserialize, Debug, Clone, ToSchema)]
+pub struct BatchProofRequest {
+    /// Shared block header and the claims to verify against it
+    #[serde(flatten)]
+    #[schema(value_type = Object)]
+    pub input: BatchProofInput,
+    /// Proof system: compressed (default), plonk or groth16
+    #[serde(default)]
+    #[schema(value_type = String)]
+    pub system: ProofSystem,
+}
+
+/// Prove several transactions from one block in a single proof
+/// Invalid claims are still proven, and reported per claim in the public values
+#[utoipa::path(
+    post,
+    path = "/prove/batch",
+    request_body = BatchProofRequest,
+    responses(
+        (status = 200, description = "Batch proof generated", body = ProofResponse),
+        (status = 400, description = "Invalid request", body = ProofResponse),
+        (status = 500, description = "Proof committed values that disagree with native verification", body = ProofResponse),
+        (status = 503, description = "Prover starting or busy", body = ProofResponse),
+        (status = 504, description = "Proof generation timed out", body = ProofResponse),
+    )
+)]
+pub async fn generate_batch_proof<P: Prover>(
+    State(state): State<AppState<P>>,
+    JsonBody(request): JsonBody<BatchProofRequest>,
+) -> Result<Json<ProofResponse>, (StatusCode, Json<ProofResponse>)> {
     let start_time = std::time::Instant::now();
 
-    info!("Generating proof");
+    if request.input.claims.is_empty() {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            ProofError::ValidationFailed("batch has no claims".to_string()),
+            start_time,
+        ));
+    }
+
+    // Verify natively first; only a malformed shared header fails the whole batch
+    let batch = verify_batch(&request.input, state.target_address()).map_err(|e| {
+        warn!("Native batch verification failed: {}", e);
+        error_response(
+            StatusCode::BAD_REQUEST,
+            ProofError::ValidationFailed(e),
+            start_time,
+        )
+    })?;
+
+    info!(
+        "Generating batch proof for {} claims ({} valid, {} BTC)",
+        batch.results.len(),
+        batch.results.iter().filter(|valid| **valid).count(),
+        sats_to_btc_string(batch.total_amount)
+    );
 
     // Setup input for the zkVM
     let mut stdin = SP1Stdin::new();
-    stdin.write(&request.tx);
-    stdin.write(&request.tx_hash);
-    stdin.write(&request.merkle);
-    stdin.write(&request.position);
-    stdin.write(&request.block_header);
-    stdin.write(&String::from(TARGET_ADDRESS));
+    stdin.write(&request.input);
+    stdin.write(&state.target_address().to_string());
+
+    let Json(mut response) =
+        prove_and_respond(&state, batch_program, request.system, stdin, start_time).await?;
+    check_batch_committed_values(&response, &request.input, state.target_address(), &batch)
+        .map_err(|e| committed_values_error(e, start_time))?;
+    response.batch = Some(batch);
+    Ok(Json(response))
+}
+
+/// Proof progress stream: emits `setup_complete`, `proving`, `verifying` and a terminal
+/// `done` (or `error`) event carrying the `ProofResponse`
+#[utoipa::path(
+    get,
+    path = "/prove/stream",
+    request_body = ProofRequest,
+    responses(
+        (status = 200, description = "Server-sent proof progress events", content_type = "text/event-stream", body = String),
+        (status = 400, description = "Invalid request", body = ProofResponse),
+        (status = 503, description = "Prover starting or busy", body = ProofResponse),
+    )
+)]
+pub async fn stream_bitcoin_proof<P: Prover>(
+    State(state): State<AppState<P>>,
+    JsonBody(request): JsonBody<ProofRequest>,
+) -> Result<Sse<ReceiverStream<Result<Event, Infallible>>>, (StatusCode, Json<ProofResponse>)> {
+    let start_time = std::time::Instant::now();
+    let (stdin, verification, expected) = prepare_proof(&request, state.target_address())
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e, start_time))?;
+    let setup = state.prover().ok_or_else(|| prover_not_ready(start_time))?;
+    let slot = state
+        .acquire_proof_slot()
+        .await
+        .ok_or_else(|| prover_busy(start_time))?;
+    let system = request.system;
+    let client = state.client();
+
+    info!("Streaming {:?} proof generation", system);
+    Ok(proof_event_stream(
+        move |on_phase, cancelled| {
+            generate_proof_with_progress(
+                &*client,
+                &setup.single,
+                system,
+                &stdin,
+                on_phase,
+                cancelled,
+            )
+        },
+        slot,
+        state.proof_stats().clone(),
+        request.tx_hash,
+        verification,
+        expected,
+        start_time,
+    ))
+}
+
+/// Execute the single-transaction program without proving and report its cycle count
+/// Much cheaper than `/prove`; use it to gauge proving time and cost
+#[utoipa::path(
+    post,
+    path = "/estimate",
+    request_body = ProofRequest,
+    responses(
+        (status = 200, description = "Cycle count, or why execution aborted", body = EstimateResponse),
+        (status = 400, description = "Invalid request", body = ProofResponse),
+        (status = 504, description = "Execution timed out", body = ProofResponse),
+    )
+)]
+pub async fn estimate_cycles<P: Prover>(
+    State(state): State<AppState<P>>,
+    JsonBody(request): JsonBody<ProofRequest>,
+) -> Result<Json<EstimateResponse>, (StatusCode, Json<ProofResponse>)> {
+    let (client, elf) = (state.client(), state.program_elf().clone());
+    estimate_with(&request, state.target_address(), move |stdin| {
+        execute_cycles(&*client, elf.bytes(), &stdin)
+    })
+    .await
+}
+
+/// Build the zkVM input for a request and run `execute` on it (blocking pool)
+async fn estimate_with<F>(
+    request: &ProofRequest,
+    target_address: &str,
+    execute: F,
+) -> Result<Json<EstimateResponse>, (StatusCode, Json<ProofResponse>)>
+where
+    F: FnOnce(SP1Stdin) -> Result<u64, anyhow::Error> + Send + 'static,
+{
+    let start_time = std::time::Instant::now();
+    let stdin = execution_stdin(request, target_address)
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e, start_time))?;
+
+    // A failed check panics the program, which ends execution without a cycle count
+    match run_with_timeout(prove_timeout(), move |_| execute(stdin)).await {
+        Ok(cycles) => {
+            info!("Execution finished in {} cycles", cycles);
+            Ok(estimate_response(Some(cycles), None, start_time))
+        }
+        Err(e @ ProofError::Timeout(_)) => {
+            warn!("{}", e);
+            Err(error_response(StatusCode::GATEWAY_TIMEOUT, e, start_time))
+        }
+        Err(ProofError::ProofGenerationFailed(msg)) => {
+            info!("Execution aborted: {}", msg);
+            Ok(estimate_response(None, Some(msg), start_time))
+        }
+        Err(e) => Ok(estimate_response(None, Some(e.to_string()), start_time)),
+    }
+}
+
+/// Execute the single-transaction program without proving and return the public values it
+/// commits, raw and decoded; for testing contracts against the exact committed bytes
+#[utoipa::path(
+    post,
+    path = "/public-values",
+    request_body = ProofRequest,
+    responses(
+        (status = 200, description = "Committed public values, or why execution aborted", body = PublicValuesResponse),
+        (status = 400, description = "Invalid request", body = ProofResponse),
+        (status = 500, description = "Public values could not be decoded", body = ProofResponse),
+        (status = 504, description = "Execution timed out", body = ProofResponse),
+    )
+)]
+pub async fn public_values<P: Prover>(
+    State(state): State<AppState<P>>,
+    JsonBody(request): JsonBody<ProofRequest>,
+) -> Result<Json<PublicValuesResponse>, (StatusCode, Json<ProofResponse>)> {
+    let (client, elf) = (state.client(), state.program_elf().clone());
+    public_values_with(&request, state.target_address(), move |stdin| {
+        execute_public_values(&*client, elf.bytes(), &stdin)
+    })
+    .await
+}
+
+/// Build the zkVM input for a request, run `execute` on it (blocking pool) and decode
+/// the committed bytes
+async fn public_values_with<F>(
+    request: &ProofRequest,
+    target_address: &str,
+    execute: F,
+) -> Result<Json<PublicValuesResponse>, (StatusCode, Json<ProofResponse>)>
+where
+    F: FnOnce(SP1Stdin) -> Result<Vec<u8>, anyhow::Error> + Send + 'static,
+{
+    let start_time = std::time::Instant::now();
+    let stdin = execution_stdin(request, target_address)
+        .map_err(|e| error_response(StatusCode::BAD_REQUEST, e, start_time))?;
+
+    let (bytes, error) = match run_with_timeout(prove_timeout(), move |_| execute(stdin)).await {
+        Ok(bytes) => (Some(bytes), None),
+        Err(e @ ProofError::Timeout(_)) => {
+            warn!("{}", e);
+            return Err(error_response(StatusCode::GATEWAY_TIMEOUT, e, start_time));
+        }
+        Err(ProofError::ProofGenerationFailed(msg)) => {
+            info!("Execution aborted: {}", msg);
+            (None, Some(msg))
+        }
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let decoded = match bytes.as_deref().map(decode_public_values) {
+        Some(Ok(decoded)) => Some(decoded),
+        Some(Err(e)) => {
+            warn!("Undecodable public values: {}", e);
+            return Err(error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ProofError::DecodeError(e),
+                start_time,
+            ));
+        }
+        None => None,
+    };
+
+    Ok(Json(PublicValuesResponse {
+        valid: bytes.is_some(),
+        public_values: bytes.map(hex::encode),
+        decoded,
+        error,
+        execution_time_ms: start_time.elapsed().as_millis() as u64,
+    }))
+}
+
+/// Check a request's txid and merkle proof, and build its zkVM input for execution
+/// Errors are all bad requests
+fn execution_stdin(request: &ProofRequest, target_address: &str) -> Result<SP1Stdin, ProofError> {
+    let input = request.proof_input().inspect_err(|e| warn!("{}", e))?;
+    let expected_op_return = parse_op_return_commitment(request.expected_op_return.as_deref())
+        .inspect_err(|e| warn!("{}", e))?;
+    Ok(proof_stdin(
+        request,
+        &input,
+        target_address,
+        expected_op_return,
+    ))
+}
+
+/// Decode the single-transaction program's public values, an ABI-encoded
+/// `PublicValuesStruct`
+fn decode_public_values(bytes: &[u8]) -> Result<DecodedPublicValues, String> {
+    let values = PublicValuesStruct::abi_decode(bytes)
+        .map_err(|e| format!("invalid public values: {}", e))?;
+    // Reject anything but the canonical encoding, e.g. trailing bytes
+    if PublicValuesStruct::abi_encode(&values) != bytes {
+        return Err("public values are not canonically encoded".to_string());
+    }
+    // Values of modes the proof was not made with are zero
+    if !values.opReturnBytesCommitted && values.opReturnBytes != 0 {
+        return Err("op_return_bytes committed without commit_op_return_bytes".to_string());
+    }
+    if !values.opReturnChecked && !values.expectedOpReturn.is_zero() {
+        return Err("expected_op_return committed without an OP_RETURN check".to_string());
+    }
+    Ok(DecodedPublicValues {
+        block_hash: values.blockHash,
+        total_amount: values.totalAmount,
+        txid: display_txid(values.txid.0),
+        output_index: values.outputIndex,
+        is_coinbase: values.isCoinbase,
+        target_address: values.targetAddress,
+        op_return_bytes: values
+            .opReturnBytesCommitted
+            .then_some(values.opReturnBytes),
+        inputs_checked: values.inputsChecked,
+        all_inputs_confirmed: values.allInputsConfirmed,
+        funding_block_hashes: values.fundingBlockHashes,
+        block_hash_pinned: values.blockHashPinned,
+        coinbase_rejected: values.coinbaseRejected,
+        no_match_allowed: values.noMatchAllowed,
+        expected_op_return: values
+            .opReturnChecked
+            .then(|| hex::encode(values.expectedOpReturn)),
+        op_return_anchored: values.opReturnAnchored,
+    })
+}
+
+/// Confirm a successful proof committed what native verification computed, with the
+/// modes the request asked for, so a prover bug cannot report success for values the
+/// request does not imply
+fn check_committed_values(
+    response: &ProofResponse,
+    txid: &str,
+    verification: &VerificationResult,
+    expected: &ExpectedValues,
+) -> Result<(), ProofError> {
+    let Some(bytes) = response.public_values.as_deref() else {
+        return Ok(());
+    };
+    let committed = decode_public_values(bytes).map_err(ProofError::DecodeError)?;
+    let mismatch =
+        |field: &str, committed: &dyn std::fmt::Display, native: &dyn std::fmt::Display| {
+            ProofError::PublicValuesMismatch(format!(
+                "committed {} {} but native verification computed {}",
+                field, committed, native
+            ))
+        };
+    let committed_modes = committed.modes();
+    if committed_modes != expected.modes {
+        return Err(ProofError::PublicValuesMismatch(format!(
+            "committed modes {:?} but the request asked for {:?}",
+            committed_modes, expected.modes
+        )));
+    }
+    if committed.block_hash != verification.block_hash {
+        return Err(mismatch(
+            "block_hash",
+            &committed.block_hash,
+            &verification.block_hash,
+        ));
+    }
+    if committed.total_amount != verification.total_amount {
+        return Err(mismatch(
+            "total_amount",
+            &committed.total_amount,
+            &verification.total_amount,
+        ));
+    }
+    let txid = txid.trim().trim_start_matches("0x");
+    if !committed.txid.eq_ignore_ascii_case(txid) {
+        return Err(mismatch("txid", &committed.txid, &txid));
+    }
+    if committed.output_index != expected.output_index {
+        return Err(mismatch(
+            "output_index",
+            &committed.output_index,
+            &expected.output_index,
+        ));
+    }
+    if committed.is_coinbase != verification.is_coinbase {
+        return Err(mismatch(
+            "is_coinbase",
+            &committed.is_coinbase,
+            &verification.is_coinbase,
+        ));
+    }
+    // Both present or both absent: the modes matched
+    if committed.op_return_bytes != expected.op_return_bytes {
+        return Err(mismatch(
+            "op_return_bytes",
+            &committed.op_return_bytes.unwrap_or_default(),
+            &expected.op_return_bytes.unwrap_or_default(),
+        ));
+    }
+    if committed.all_inputs_confirmed != expected.all_inputs_confirmed {
+        return Err(mismatch(
+            "all_inputs_confirmed",
+            &committed.all_inputs_confirmed,
+            &expected.all_inputs_confirmed,
+        ));
+    }
+    if committed.funding_block_hashes != expected.funding_block_hashes {
+        return Err(mismatch(
+            "funding_block_hashes",
+            &committed.funding_block_hashes.join(","),
+            &expected.funding_block_hashes.join(","),
+        ));
+    }
+    if committed.op_return_anchored != expected.op_return_anchored {
+        return Err(mismatch(
+            "op_return_anchored",
+            &committed.op_return_anchored,
+            &expected.op_return_anchored,
+        ));
+    }
+    Ok(())
+}
+
+/// Public values committed by the batch program
+#[derive(Debug, PartialEq, Eq)]
+struct DecodedBatchValues {
+    block_hash: String,
+    target_address: String,
+    all_valid: bool,
+    /// (txid in display hex, valid), in claim order
+    results: Vec<(String, bool)>,
+    total_amount: u64,
+}
+
+/// Decode the batch program's ABI-encoded `BatchPublicValuesStruct`
+fn decode_batch_public_values(bytes: &[u8]) -> Result<DecodedBatchValues, String> {
+    let values = BatchPublicValuesStruct::abi_decode(bytes)
+        .map_err(|e| format!("invalid batch public values: {}", e))?;
+    // Reject anything but the canonical encoding, e.g. trailing bytes
+    if BatchPublicValuesStruct::abi_encode(&values) != bytes {
+        return Err("batch public values are not canonically encoded".to_string());
+    }
+    if values.txids.len() != values.results.len() {
+        return Err(format!(
+            "{} txids but {} results committed",
+            values.txids.len(),
+            values.results.len()
+        ));
+    }
+    let results = values
+        .txids
+        .iter()
+        .map(|txid| display_txid(txid.0))
+        .zip(values.results)
+        .collect();
+    Ok(DecodedBatchValues {
+        block_hash: values.blockHash,
+        target_address: values.targetAddress,
+        all_valid: values.allValid,
+        results,
+        total_amount: values.totalAmount,
+    })
+}
+
+/// Display hex of a txid in internal byte order
+fn display_txid(mut txid: [u8; 32]) -> String {
+    txid.reverse();
+    hex::encode(txid)
+}
+
+/// Confirm a successful batch proof committed the claims, target and outcome native
+/// verification computed
+fn check_batch_committed_values(
+    response: &ProofResponse,
+    input: &BatchProofInput,
+    target_address: &str,
+    expected: &BatchResult,
+) -> Result<(), ProofError> {
+    let Some(bytes) = response.public_values.as_deref() else {
+        return Ok(());
+    };
+    let committed = decode_batch_public_values(bytes).map_err(ProofError::DecodeError)?;
+    let native = DecodedBatchValues {
+        block_hash: expected.block_hash.clone(),
+        target_address: target_address.to_string(),
+        all_valid: expected.all_valid,
+        results: input
+            .claims
+            .iter()
+            .map(|claim| display_txid(claim.txid_bytes()))
+            .zip(expected.results.iter().copied())
+            .collect(),
+        total_amount: expected.total_amount,
+    };
+    if committed != native {
+        return Err(ProofError::PublicValuesMismatch(format!(
+            "committed {:?} but native verification computed {:?}",
+            committed, native
+        )));
+    }
+    Ok(())
+}
+
+fn estimate_response(
+    cycles: Option<u64>,
+    error: Option<String>,
+    start_time: std::time::Instant,
+) -> Json<EstimateResponse> {
+    Json(EstimateResponse {
+        valid: cycles.is_some(),
+        cycles,
+        error,
+        execution_time_ms: start_time.elapsed().as_millis() as u64,
+    })
+}
+
+/// Validate a proof request natively and build the zkVM input for it, with the values
+/// the proof must commit
+/// Errors are all bad requests
+fn prepare_proof(
+    request: &ProofRequest,
+    target_address: &str,
+) -> Result<(SP1Stdin, VerificationResult, ExpectedValues), ProofError> {
+    let input = request.proof_input().inspect_err(|e| warn!("{}", e))?;
+    let expected_op_return = parse_op_return_commitment(request.expected_op_return.as_deref())
+        .inspect_err(|e| warn!("{}", e))?;
+    if request.check_siblings {
+        check_merkle_siblings(request, &input.merkle_siblings, input.pos)
+            .inspect_err(|e| warn!("{}", e))?;
+    }
+
+    // Verify natively first so invalid input fails fast instead of inside the prover
+    let verification = verify_tx_in_block_and_outputs(
+        &input.tx_hex,
+        &input.txid,
+        input.merkle_siblings.clone(),
+        input.pos,
+        &input.block_header,
+        target_address,
+        request.expected_block_hash.as_deref(),
+        request.reject_coinbase,
+        request.allow_no_match,
+    )
+    .map_err(|e| {
+        warn!("Native verification failed: {}", e);
+        ProofError::ValidationFailed(e)
+    })?;
+    info!(
+        "Native verification passed: {} BTC to target",
+        verification.total_amount_btc
+    );
+
+    let expected = ExpectedValues::compute(
+        &input.tx_hex,
+        &verification,
+        request.modes(target_address, expected_op_return),
+        &request.funding_proofs,
+        expected_op_return,
+    )
+    .inspect_err(|e| warn!("{}", e))?;
+
+    Ok((
+        proof_stdin(request, &input, target_address, expected_op_return),
+        verification,
+        expected,
+    ))
+}
+
+/// zkVM input of the single-transaction program for a request
+fn proof_stdin(
+    request: &ProofRequest,
+    input: &ProofInput,
+    target_address: &str,
+    expected_op_return: Option<[u8; 32]>,
+) -> SP1Stdin {
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&input.tx_hex);
+    stdin.write(&input.txid);
+    stdin.write(&input.merkle_siblings);
+    stdin.write(&input.pos);
+    stdin.write(&input.block_header);
+    stdin.write(&target_address.to_string());
+    stdin.write(&request.commit_op_return_bytes);
+    stdin.write(&request.funding_proofs);
+    stdin.write(&request.expected_block_hash);
+    stdin.write(&request.reject_coinbase);
+    stdin.write(&request.allow_no_match);
+    stdin.write(&expected_op_return);
+    stdin
+}
+
+/// The 32-byte value an OP_RETURN output must carry, from its hex form
+fn parse_op_return_commitment(value: Option<&str>) -> Result<Option<[u8; 32]>, ProofError> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    let value = value.trim();
+    let bytes = hex::decode(value.strip_prefix("0x").unwrap_or(value)).map_err(|e| {
+        ProofError::ValidationFailed(format!("invalid expected_op_return hex: {}", e))
+    })?;
+    let commitment = bytes.try_into().map_err(|bytes: Vec<u8>| {
+        ProofError::ValidationFailed(format!(
+            "expected_op_return must be 32 bytes, got {}",
+            bytes.len()
+        ))
+    })?;
+    Ok(Some(commitment))
+}
+
+/// Reject siblings that do not reconstruct the header's merkle root, with a hint
+/// at the likely mistake
+fn check_merkle_siblings(
+    request: &ProofRequest,
+    merkle: &[String],
+    position: usize,
+) -> Result<(), ProofError> {
+    match diagnose_merkle_proof(&request.tx, merkle, position, &request.block_header) {
+        Ok(None) => Ok(()),
+        Ok(Some(hint)) | Err(hint) => Err(ProofError::InvalidMerkleSiblings(hint)),
+    }
+}
+
+/// Reject a txid that is not exactly 64 hex characters, before it is put in an explorer URL
+fn validate_txid(txid: &str) -> Result<(), ProofError> {
+    if txid.len() == 64 && txid.bytes().all(|b| b.is_ascii_hexdigit()) {
+        Ok(())
+    } else {
+        Err(ProofError::InvalidHex(format!(
+            "txid must be 64 hex characters: {}",
+            txid
+        )))
+    }
+}
+
+/// Reject a target address that is malformed or encoded for another network,
+/// before anything is fetched
+fn validate_target_address(address: &str, network: &str) -> Result<(), ProofError> {
+    let expected = match network {
+        "mainnet" | "bitcoin" => Network::Mainnet,
+        "testnet" => Network::Testnet,
+        "signet" => Network::Signet,
+        other => {
+            return Err(ProofError::ValidationFailed(format!(
+                "unsupported network: {}",
+                other
+            )))
+        }
+    };
+    if is_valid_address(address, expected) {
+        Ok(())
+    } else {
+        Err(ProofError::ValidationFailed(format!(
+            "target_address {} is not a valid {} address",
+            address, network
+        )))
+    }
+}
+
+/// Build an error response with the elapsed time
+pub(crate) fn error_response(
+    status: StatusCode,
+    error: ProofError,
+    start_time: std::time::Instant,
+) -> (StatusCode, Json<ProofResponse>) {
+    (
+        status,
+        Json(ProofResponse {
+            success: false,
+            error: Some(error.to_string()),
+            error_code: Some(error.code().to_string()),
+            public_values: None,
+            proof_bytes: None,
+            execution_time_ms: Some(start_time.elapsed().as_millis() as u64),
+            verification: None,
+            batch: None,
+            request_id: current_request_id(),
+        }),
+    )
+}
+
+/// Default upper bound on a single proof generation
+const DEFAULT_PROVE_TIMEOUT_SECS: u64 = 1800;
+
+/// Proof generation timeout from `PROVE_TIMEOUT_SECS`
+fn prove_timeout() -> Duration {
+    let secs = std::env::var("PROVE_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_PROVE_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Sets a job's cancel flag when dropped
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Run CPU-bound proving work on the blocking pool, giving up after `timeout`
+/// A job already running cannot be interrupted (SP1 has no cancellation hook); on timeout
+/// its cancel flag is set, which the job checks between phases to stop early, but until
+/// it next checks it keeps its blocking thread and CPU
+async fn run_with_timeout<T, F>(timeout: Duration, job: F) -> Result<T, ProofError>
+where
+    T: Send + 'static,
+    F: FnOnce(&AtomicBool) -> Result<T, anyhow::Error> + Send + 'static,
+{
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let job_cancelled = cancelled.clone();
+    let mut handle = tokio::task::spawn_blocking(move || job(&job_cancelled));
+    // An aborted caller (e.g. a cancelled proof job) cancels the job as well
+    let _cancel_on_drop = CancelOnDrop(cancelled.clone());
+    match tokio::time::timeout(timeout, &mut handle).await {
+        Ok(Ok(result)) => result.map_err(|e| ProofError::ProofGenerationFailed(e.to_string())),
+        Ok(Err(e)) => Err(ProofError::ProofGenerationFailed(format!(
+            "proving task failed: {}",
+            e
+        ))),
+        Err(_) => {
+            // A job that has not started yet never runs; a running one sees the flag
+            cancelled.store(true, Ordering::Relaxed);
+            handle.abort();
+            Err(ProofError::Timeout(format!(
+                "exceeded {}s",
+                timeout.as_secs_f64()
+            )))
+        }
+    }
+}
+
+/// Generate the proof for prepared zkVM input and wrap the outcome in a response
+/// Timeouts are a 504; other proving failures are reported in the response body
+async fn prove_and_respond<P: Prover>(
+    state: &AppState<P>,
+    program: fn(&ProverSetup<P>) -> &ProgramKeys<P>,
+    system: ProofSystem,
+    stdin: SP1Stdin,
+    start_time: std::time::Instant,
+) -> Result<Json<ProofResponse>, (StatusCode, Json<ProofResponse>)> {
+    let setup = state.prover().ok_or_else(|| prover_not_ready(start_time))?;
+    // The slot is released when this request ends, timeouts included, so a timed-out
+    // job still finishing its current phase briefly runs beyond the limiter's count
+    let _slot = state
+        .acquire_proof_slot()
+        .await
+        .ok_or_else(|| prover_busy(start_time))?;
 
     // Generate proof using the zkVM
-    match generate_proof_internal(&stdin).await {
+    let client = state.client();
+    match run_with_timeout(prove_timeout(), move |cancelled| {
+        generate_proof_internal(&*client, program(&setup), system, &stdin, cancelled)
+    })
+    .await
+    {
         Ok(public_values) => {
             let execution_time = start_time.elapsed().as_millis() as u64;
+            state.proof_stats().record(true, execution_time);
             info!("Proof Generated");
             Ok(Json(ProofResponse {
                 success: true,
                 error: None,
+                error_code: None,
                 public_values: Some(public_values),
                 proof_bytes: None,
                 execution_time_ms: Some(execution_time),
+                verification: None,
+                batch: None,
+                request_id: current_request_id(),
             }))
         }
+        Err(e @ ProofError::Timeout(_)) => {
+            warn!("{}", e);
+            let elapsed = start_time.elapsed().as_millis() as u64;
+            state.proof_stats().record(false, elapsed);
+            Err(error_response(StatusCode::GATEWAY_TIMEOUT, e, start_time))
+        }
         Err(e) => {
             let execution_time = start_time.elapsed().as_millis() as u64;
+            state.proof_stats().record(false, execution_time);
             warn!("Proof generation failed: {}", e);
 
             Ok(Json(ProofResponse {
                 success: false,
-                error: Some(ProofError::ProofGenerationFailed(e.to_string()).to_string()),
+                error: Some(e.to_string()),
+                error_code: Some(e.code().to_string()),
                 public_values: None,
                 proof_bytes: None,
                 execution_time_ms: Some(execution_time),
+                verification: None,
+                batch: None,
+                request_id: current_request_id(),
             }))
         }
     }
 }
 
-/// Internal proof generation logic using SP1 zkVM
-async fn generate_proof_internal(stdin: &SP1Stdin) -> Result<Vec<u8>, anyhow::Error> {
-    // Initialize the SP1 prover client
-    let client = ProverClient::from_env();
+/// Prove prepared single-transaction input and check the proof committed the values
+/// native verification computed, attaching that verification to the response
+async fn prove_single<P: Prover>(
+    state: &AppState<P>,
+    system: ProofSystem,
+    stdin: SP1Stdin,
+    txid: &str,
+    verification: VerificationResult,
+    expected: &ExpectedValues,
+    start_time: std::time::Instant,
+) -> Result<ProofResponse, (StatusCode, Json<ProofResponse>)> {
+    let Json(mut response) =
+        prove_and_respond(state, single_program, system, stdin, start_time).await?;
+    check_committed_values(&response, txid, &verification, expected)
+        .map_err(|e| committed_values_error(e, start_time))?;
+    response.verification = Some(verification);
+    Ok(response)
+}
 
-    // Setup the program for proving (generate proving key and verification key)
-    let (proving_key, verification_key) = client.setup(BITCOIN_PROOF_ELF);
+/// Proving phases reported on the progress stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProvePhase {
+    SetupComplete,
+    Proving,
+    Verifying,
+}
 
-    // Generate the zero-knowledge proof
-    let proof = client
-        .prove(&proving_key, stdin)
-        .run()
-        .map_err(|e| anyhow::anyhow!("Failed to generate proof: {}", e))?;
+impl ProvePhase {
+    /// SSE event name for the phase
+    fn event_name(self) -> &'static str {
+        match self {
+            ProvePhase::SetupComplete => "setup_complete",
+            ProvePhase::Proving => "proving",
+            ProvePhase::Verifying => "verifying",
+        }
+    }
+}
+
+/// Run `prover` on the blocking pool, forwarding its phase transitions as SSE events
+/// and finishing with a `done` or `error` event
+/// `done` is only sent once the committed values match native verification of `txid`
+/// `slot` is held until the proof finishes or times out; the attempt is recorded in `stats`
+fn proof_event_stream<F, S>(
+    prover: F,
+    slot: S,
+    stats: Arc<ProofStats>,
+    txid: String,
+    verification: VerificationResult,
+    expected: ExpectedValues,
+    start_time: std::time::Instant,
+) -> Sse<ReceiverStream<Result<Event, Infallible>>>
+where
+    F: FnOnce(&dyn Fn(ProvePhase), &AtomicBool) -> Result<Vec<u8>, anyhow::Error> + Send + 'static,
+    S: Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(8);
+    // The spawned task outlives the request scope, so carry its id and span along
+    let request_id = current_request_id();
+
+    tokio::spawn(
+        async move {
+            let phase_tx = tx.clone();
+            let result = run_with_timeout(prove_timeout(), move |cancelled| {
+                prover(
+                    &|phase: ProvePhase| {
+                        // A disconnected client only loses progress updates
+                        let _ = phase_tx.blocking_send(Ok(Event::default()
+                            .event(phase.event_name())
+                            .data(phase.event_name())));
+                    },
+                    cancelled,
+                )
+            })
+            .await;
+            drop(slot);
+
+            let execution_time = start_time.elapsed().as_millis() as u64;
+            stats.record(result.is_ok(), execution_time);
+            let failure = |e: ProofError| ProofResponse {
+                success: false,
+                error: Some(e.to_string()),
+                error_code: Some(e.code().to_string()),
+                public_values: None,
+                proof_bytes: None,
+                execution_time_ms: Some(execution_time),
+                verification: None,
+                batch: None,
+                request_id: request_id.clone(),
+            };
+            let (name, response) = match result {
+                Ok(public_values) => {
+                    let response = ProofResponse {
+                        success: true,
+                        error: None,
+                        error_code: None,
+                        public_values: Some(public_values),
+                        proof_bytes: None,
+                        execution_time_ms: Some(execution_time),
+                        verification: None,
+                        batch: None,
+                        request_id: request_id.clone(),
+                    };
+                    match check_committed_values(&response, &txid, &verification, &expected) {
+                        Ok(()) => {
+                            info!("Proof Generated");
+                            let verification = Some(verification);
+                            (
+                                "done",
+                                ProofResponse {
+                                    verification,
+                                    ..response
+                                },
+                            )
+                        }
+                        Err(e) => {
+                            error!("{}", e);
+                            ("error", failure(e))
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("Proof generation failed: {}", e);
+                    let verification = Some(verification);
+                    (
+                        "error",
+                        ProofResponse {
+                            verification,
+                            ..failure(e)
+                        },
+                    )
+                }
+            };
+            let data = serde_json::to_string(&response).expect("ProofResponse serializes");
+            let _ = tx.send(Ok(Event::default().event(name).data(data))).await;
+        }
+        .in_current_span(),
+    );
 
-    let public_values = proof.public_values.as_slice();
+    Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default())
+}
+
+/// Keys of the single-transaction program
+fn single_program<P: Prover>(setup: &ProverSetup<P>) -> &ProgramKeys<P> {
+    &setup.single
+}
+
+/// Keys of the batch program
+fn batch_program<P: Prover>(setup: &ProverSetup<P>) -> &ProgramKeys<P> {
+    &setup.batch
+}
+
+/// 503 response while prover setup is still running
+fn prover_not_ready(start_time: std::time::Instant) -> (StatusCode, Json<ProofResponse>) {
+    warn!("Proof requested before prover setup finished");
+    error_response(
+        StatusCode::SERVICE_UNAVAILABLE,
+        ProofError::ProverNotReady("setup still running".to_string()),
+        start_time,
+    )
+}
 
-    // Decode the public values
-    // Format: [8-byte length][block_hash string][8-byte total_amount]
-    if public_values.len() < 8 {
-        return Err(anyhow::anyhow!("Invalid public values: too short"));
+/// SP1 proof mode for a requested proof system
+fn proof_mode(system: ProofSystem) -> SP1ProofMode {
+    match system {
+        ProofSystem::Compressed => SP1ProofMode::Compressed,
+        ProofSystem::Plonk => SP1ProofMode::Plonk,
+        ProofSystem::Groth16 => SP1ProofMode::Groth16,
     }
+}
+
+/// 500 response for a proof whose public values disagree with native verification,
+/// a server fault
+fn committed_values_error(
+    error: ProofError,
+    start_time: std::time::Instant,
+) -> (StatusCode, Json<ProofResponse>) {
+    error!("{}", error);
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, error, start_time)
+}
+
+/// 503 response while the proof queue is full
+fn prover_busy(start_time: std::time::Instant) -> (StatusCode, Json<ProofResponse>) {
+    warn!("Proof queue full, rejecting request");
+    error_response(
+        StatusCode::SERVICE_UNAVAILABLE,
+        ProofError::ProverBusy("too many proofs in progress, retry later".to_string()),
+        start_time,
+    )
+}
+
+/// Execute `elf` on `stdin` without proving and return the total cycle count (blocking)
+fn execute_cycles(
+    client: &impl Prover,
+    elf: &[u8],
+    stdin: &SP1Stdin,
+) -> Result<u64, anyhow::Error> {
+    let (_, cycles) = client
+        .execute(elf, stdin)
+        .map_err(|e| anyhow::anyhow!("Execution failed: {}", e))?;
+    Ok(cycles)
+}
+
+/// Execute a program without proving and return its committed public values (blocking)
+fn execute_public_values(
+    client: &impl Prover,
+    elf: &[u8],
+    stdin: &SP1Stdin,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let (public_values, _) = client
+        .execute(elf, stdin)
+        .map_err(|e| anyhow::anyhow!("Execution failed: {}", e))?;
+    Ok(public_values)
+}
+
+/// Internal proof generation logic using SP1 zkVM (blocking)
+fn generate_proof_internal<P: Prover>(
+    client: &P,
+    keys: &ProgramKeys<P>,
+    system: ProofSystem,
+    stdin: &SP1Stdin,
+    cancelled: &AtomicBool,
+) -> Result<Vec<u8>, anyhow::Error> {
+    generate_proof_with_progress(client, keys, system, stdin, &|_| {}, cancelled)
+}
+
+/// Proof generation reporting each phase transition to `on_phase` (blocking)
+fn generate_proof_with_progress<P: Prover>(
+    client: &P,
+    keys: &ProgramKeys<P>,
+    system: ProofSystem,
+    stdin: &SP1Stdin,
+    on_phase: &dyn Fn(ProvePhase),
+    cancelled: &AtomicBool,
+) -> Result<Vec<u8>, anyhow::Error> {
+    // Keys come from the cached setup
+    on_phase(ProvePhase::SetupComplete);
 
-    // Read the length of the block_hash string (first 8 bytes as u64)
-    let block_hash_len = u64::from_le_bytes([
-        public_values[0],
-        public_values[1],
-        public_values[2],
-        public_values[3],
-        public_values[4],
-        public_values[5],
-        public_values[6],
-        public_values[7],
-    ]) as usize;
+    prove_with_backend(
+        client,
+        keys,
+        system,
+        stdin,
+        verify_after_prove(),
+        on_phase,
+        cancelled,
+    )
+}
 
-    if public_values.len() < 8 + block_hash_len + 8 {
-        return Err(anyhow::anyhow!("Invalid public values: insufficient data"));
+/// Error out once the caller has given up on the job
+fn check_cancelled(cancelled: &AtomicBool) -> Result<(), anyhow::Error> {
+    if cancelled.load(Ordering::Relaxed) {
+        return Err(anyhow::anyhow!("cancelled after the request timed out"));
     }
+    Ok(())
+}
+
+/// Prove on `backend`, then verify locally unless `verify` is off (blocking)
+/// Stops before each phase once `cancelled` is set
+fn prove_with_backend<B: Prover>(
+    backend: &B,
+    keys: &ProgramKeys<B>,
+    system: ProofSystem,
+    stdin: &SP1Stdin,
+    verify: bool,
+    on_phase: &dyn Fn(ProvePhase),
+    cancelled: &AtomicBool,
+) -> Result<Vec<u8>, anyhow::Error> {
+    // Generate the zero-knowledge proof
+    check_cancelled(cancelled)?;
+    on_phase(ProvePhase::Proving);
+    let proof = backend
+        .prove(&keys.proving_key, stdin, proof_mode(system))
+        .map_err(|e| anyhow::anyhow!("Failed to generate proof: {}", e))?;
+
+    let public_values = backend.public_values(&proof);
 
     // Verify the generated proof locally
-    client
-        .verify(&proof, &verification_key)
-        .map_err(|e| anyhow::anyhow!("Failed to verify proof: {}", e))?;
+    if verify {
+        check_cancelled(cancelled)?;
+        on_phase(ProvePhase::Verifying);
+        backend
+            .verify(&proof, &keys.verifying_key)
+            .map_err(|e| anyhow::anyhow!("Failed to verify proof: {}", e))?;
+    }
 
     Ok(public_values.to_vec())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::jobs::JobStatus;
+    use crate::server::limits::ProofLimiter;
+    use crate::server::prover::{MockProver, ProverBackend};
+    use crate::TARGET_ADDRESS;
+    use fibonacci_lib::{compress_merkle_proof_hex, fixtures, siblings_from_hex, TxClaim};
+    use sp1_sdk::SP1PublicValues;
+
+    /// State proving instantly with a mock committing `public_values`; no SP1 setup runs
+    fn mock_state(public_values: Vec<u8>) -> AppState<MockProver> {
+        AppState::new(MockProver::new(public_values))
+    }
+
+    /// What the program commits for `sample_request` paying the fixture's target
+    fn sample_public_values() -> PublicValuesStruct {
+        let mut txid: [u8; 32] = hex::decode(fixtures::TXID).unwrap().try_into().unwrap();
+        txid.reverse();
+        PublicValuesStruct {
+            blockHash: fixtures::BLOCK_HASH.to_string(),
+            totalAmount: 1_240_000_000,
+            txid: txid.into(),
+            outputIndex: target_outpoint(fixtures::TX_HEX, fixtures::TARGET_ADDRESS)
+                .unwrap()
+                .1,
+            isCoinbase: false,
+            targetAddress: fixtures::TARGET_ADDRESS.to_string(),
+            opReturnBytesCommitted: false,
+            opReturnBytes: 0,
+            inputsChecked: false,
+            allInputsConfirmed: false,
+            fundingBlockHashes: vec![],
+            blockHashPinned: false,
+            coinbaseRejected: false,
+            noMatchAllowed: false,
+            opReturnChecked: false,
+            expectedOpReturn: Default::default(),
+            opReturnAnchored: false,
+        }
+    }
+
+    fn sample_request() -> ProofRequest {
+        ProofRequest {
+            tx: fixtures::TX_HEX.to_string(),
+            tx_hash: fixtures::TXID.to_string(),
+            merkle: fixtures::merkle_siblings(),
+            position: fixtures::POSITION,
+            proof_blob: None,
+            block_header: fixtures::BLOCK_HEADER.to_string(),
+            commit_op_return_bytes: false,
+            funding_proofs: vec![],
+            system: ProofSystem::default(),
+            expected_block_hash: None,
+            reject_coinbase: false,
+            allow_no_match: false,
+            expected_op_return: None,
+            check_siblings: false,
+        }
+    }
+
+    #[test]
+    fn test_canonical_request_bytes_ignore_field_order() {
+        let a: ProofRequest = serde_json::from_str(
+            r#"{"tx": "0100", "tx_hash": "AB", "merkle": ["01", "02"], "position": 3,
+                "block_header": "ff", "system": "plonk"}"#,
+        )
+        .unwrap();
+        let b: ProofRequest = serde_json::from_str(
+            r#"{"system": "plonk", "block_header": "FF", "position": 3,
+                "merkle": ["01", "02"], "tx_hash": "0xab", "tx": "0100", "check_siblings": true}"#,
+        )
+        .unwrap();
+        assert_eq!(canonical_request_bytes(&a), canonical_request_bytes(&b));
+
+        // Sibling order does not change the proven statement
+        let mut a = a;
+        a.merkle.reverse();
+        assert_eq!(canonical_request_bytes(&a), canonical_request_bytes(&b));
+        a.position = 2;
+        assert_ne!(canonical_request_bytes(&a), canonical_request_bytes(&b));
+
+        // A proof_blob keys the same as the merkle list it encodes
+        let listed = sample_request();
+        let mut blob = sample_request();
+        blob.proof_blob = Some(compress_merkle_proof_hex(&blob.merkle, blob.position).unwrap());
+        blob.merkle = vec![];
+        blob.position = 0;
+        assert_eq!(
+            canonical_request_bytes(&listed),
+            canonical_request_bytes(&blob)
+        );
+    }
+
+    #[test]
+    fn test_proof_request_system() {
+        let body = serde_json::json!({
+            "tx": "00",
+            "tx_hash": "00",
+            "merkle": [],
+            "position": 0,
+            "block_header": "00",
+        });
+        let request: ProofRequest = serde_json::from_value(body.clone()).unwrap();
+        assert_eq!(request.system, ProofSystem::Compressed);
+
+        let mut with_system = body.clone();
+        with_system["system"] = "groth16".into();
+        let request: ProofRequest = serde_json::from_value(with_system).unwrap();
+        assert_eq!(request.system, ProofSystem::Groth16);
+
+        let mut unknown = body;
+        unknown["system"] = "stark".into();
+        assert!(serde_json::from_value::<ProofRequest>(unknown).is_err());
+
+        assert_eq!(
+            proof_mode(ProofSystem::Compressed),
+            SP1ProofMode::Compressed
+        );
+        assert_eq!(proof_mode(ProofSystem::Plonk), SP1ProofMode::Plonk);
+        assert_eq!(proof_mode(ProofSystem::Groth16), SP1ProofMode::Groth16);
+    }
+
+    #[tokio::test]
+    async fn test_verify_offchain_accepts_proof_blob() {
+        let Json(expected) =
+            verify_offchain(State(mock_state(Vec::new())), JsonBody(sample_request()))
+                .await
+                .unwrap();
+
+        let mut request = sample_request();
+        request.proof_blob =
+            Some(compress_merkle_proof_hex(&request.merkle, request.position).unwrap());
+        request.merkle.clear();
+        request.position = 0;
+        let Json(report) = verify_offchain(State(mock_state(Vec::new())), JsonBody(request))
+            .await
+            .unwrap();
+        assert_eq!(report, expected);
+
+        // The blob and the sibling list are mutually exclusive
+        let mut both = sample_request();
+        both.proof_blob = Some(compress_merkle_proof_hex(&both.merkle, both.position).unwrap());
+        let (status, Json(response)) =
+            verify_offchain(State(mock_state(Vec::new())), JsonBody(both))
+                .await
+                .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(response.error_code.as_deref(), Some("VALIDATION_FAILED"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_offchain_returns_report() {
+        let Json(report) =
+            verify_offchain(State(mock_state(Vec::new())), JsonBody(sample_request()))
+                .await
+                .unwrap();
+
+        assert_eq!(report.txid, fixtures::TXID);
+        assert!(report.txid_valid);
+        assert!(report.merkle_valid);
+        assert!(report.pow_valid);
+        assert_eq!(report.outputs.len(), 4);
+        // The mainnet sample does not pay the server's testnet target address
+        assert!(!report.outputs_valid);
+
+        // Malformed input is a bad request
+        let mut request = sample_request();
+        request.block_header = "zz".to_string();
+        let (status, Json(response)) =
+            verify_offchain(State(mock_state(Vec::new())), JsonBody(request))
+                .await
+                .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(response.error_code.as_deref(), Some("VALIDATION_FAILED"));
+        assert!(!response.success);
+    }
+
+    #[tokio::test]
+    async fn test_fields_query_filters_proof_response() {
+        let response = ProofResponse {
+            success: true,
+            error: None,
+            error_code: None,
+            public_values: Some(vec![1, 2, 3]),
+            proof_bytes: Some(vec![4, 5]),
+            execution_time_ms: Some(42),
+            verification: None,
+            batch: None,
+            request_id: None,
+        };
+
+        // Only success and proof are serialized
+        let fields = parse_fields(Some("success,proof")).unwrap();
+        let value = filter_fields(&response, fields.as_deref());
+        assert_eq!(
+            value,
+            serde_json::json!({ "success": true, "proof_bytes": [4, 5] })
+        );
+
+        // No fields parameter keeps the full response
+        let value = filter_fields(&response, None);
+        assert_eq!(
+            value.as_object().unwrap().len(),
+            PROOF_RESPONSE_FIELDS.len()
+        );
+
+        // Unknown fields are rejected before any proving starts
+        let query = FieldsQuery {
+            fields: Some("success,signature".to_string()),
+        };
+        assert_eq!(
+            generate_bitcoin_proof(
+                State(mock_state(Vec::new())),
+                Query(query),
+                JsonBody(sample_request())
+            )
+            .await
+            .unwrap_err()
+            .0,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prove_fails_fast_on_native_verification() {
+        // The mainnet sample does not pay the server's target address
+        let (status, Json(response)) = generate_bitcoin_proof(
+            State(mock_state(Vec::new())),
+            Query(FieldsQuery::default()),
+            JsonBody(sample_request()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(!response.success);
+        assert!(response.error.unwrap().starts_with("Validation failed"));
+        assert!(response.verification.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_prove_timeout_fires_for_slow_prover() {
+        // Fake prover that takes far longer than the timeout, then reports whether it
+        // saw the cancel flag
+        let (seen_tx, seen_rx) = std::sync::mpsc::channel();
+        let slow_prover = move |cancelled: &AtomicBool| {
+            std::thread::sleep(Duration::from_millis(500));
+            seen_tx.send(cancelled.load(Ordering::Relaxed)).unwrap();
+            Ok(vec![1u8])
+        };
+        let result = run_with_timeout(Duration::from_millis(50), slow_prover).await;
+        assert!(matches!(result, Err(ProofError::Timeout(_))));
+        let seen = tokio::task::spawn_blocking(move || seen_rx.recv().unwrap());
+        assert!(seen.await.unwrap());
+
+        // A fast prover completes within the timeout
+        let result = run_with_timeout(Duration::from_secs(5), |_| Ok(vec![7u8])).await;
+        assert_eq!(result.unwrap(), vec![7u8]);
+
+        // Prover errors stay distinct from timeouts
+        let result: Result<Vec<u8>, _> = run_with_timeout(Duration::from_secs(5), |_| {
+            Err(anyhow::anyhow!("bad input"))
+        })
+        .await;
+        assert!(matches!(result, Err(ProofError::ProofGenerationFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_prove_rejects_mismatched_tx_hash() {
+        let mut request = sample_request();
+        request.tx_hash =
+            "c02522c109410dafd1a361b3ebbec4f7da8d6a8b34f35fcf9c2ce07cc2bb132f".to_string();
+
+        let (status, Json(response)) = generate_bitcoin_proof(
+            State(mock_state(Vec::new())),
+            Query(FieldsQuery::default()),
+            JsonBody(request),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let error = response.error.unwrap();
+        assert!(
+            error.contains("does not match the transaction's txid"),
+            "{}",
+            error
+        );
+        assert!(error.contains(fixtures::TXID));
+
+        // Matching pair passes, in either hex case
+        let mut request = sample_request();
+        request.tx_hash = request.tx_hash.to_uppercase();
+        assert!(request.proof_input().is_ok());
+        request.tx_hash = format!("0x{}", fixtures::TXID);
+        assert!(request.proof_input().is_ok());
+    }
+
+    #[test]
+    fn test_proof_input_matches_lib() {
+        let cases: Vec<fn(&mut ProofRequest)> = vec![
+            |_| {},
+            |r| r.tx_hash = r.tx_hash.to_uppercase(),
+            |r| r.merkle = r.merkle.iter().map(|s| format!("0x{}", s)).collect(),
+            |r| r.tx_hash = "11".repeat(32),
+            |r| r.merkle[2].truncate(60),
+            |r| r.position = 1 << 11,
+            |r| r.block_header.truncate(150),
+            |r| r.tx = "zz".to_string(),
+        ];
+        for (i, modify) in cases.into_iter().enumerate() {
+            let mut request = sample_request();
+            modify(&mut request);
+            let lib = prepare_proof_input(
+                &request.tx,
+                &request.tx_hash,
+                &request.merkle,
+                request.position,
+                &request.block_header,
+            );
+            match (request.proof_input(), lib) {
+                (Ok(server), Ok(lib)) => assert_eq!(server, lib, "case {}", i),
+                (Err(ProofError::ValidationFailed(server)), Err(lib)) => {
+                    assert_eq!(server, lib, "case {}", i)
+                }
+                (server, lib) => panic!("case {}: server {:?}, lib {:?}", i, server, lib),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prove_by_txid_rejects_bad_target_address() {
+        let request = |target_address: &str, network: &str| ProveByTxidRequest {
+            txid: fixtures::TXID.to_string(),
+            target_address: target_address.to_string(),
+            network: network.to_string(),
+            commit_op_return_bytes: false,
+            system: ProofSystem::default(),
+            expected_block_hash: None,
+            reject_coinbase: false,
+            allow_no_match: false,
+            expected_op_return: None,
+        };
+
+        // Corrupted checksum, and a mainnet address on testnet; neither reaches the explorer
+        for (target_address, network) in [
+            ("1BUBQuPV3gEV7P2XLNuAJQjf5t265Yyj9q", "mainnet"),
+            ("1BUBQuPV3gEV7P2XLNuAJQjf5t265Yyj9t", "testnet"),
+        ] {
+            let (status, Json(response)) = generate_bitcoin_proof_by_txid(
+                State(mock_state(Vec::new())),
+                Query(FieldsQuery::default()),
+                JsonBody(request(target_address, network)),
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            let error = response.error.unwrap();
+            assert!(error.contains("is not a valid"), "{}", error);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prove_by_txid_rejects_malformed_txid() {
+        // Short, non-hex and path-injecting txids never reach the explorer
+        for txid in [
+            &fixtures::TXID[..63],
+            &format!("{}zz", &fixtures::TXID[..62]),
+            &format!("{}/../blocks", fixtures::TXID),
+        ] {
+            let request = ProveByTxidRequest {
+                txid: txid.to_string(),
+                target_address: fixtures::TARGET_ADDRESS.to_string(),
+                network: "mainnet".to_string(),
+                commit_op_return_bytes: false,
+                system: ProofSystem::default(),
+                expected_block_hash: None,
+                reject_coinbase: false,
+                allow_no_match: false,
+                expected_op_return: None,
+            };
+            let (status, Json(response)) = generate_bitcoin_proof_by_txid(
+                State(mock_state(Vec::new())),
+                Query(FieldsQuery::default()),
+                JsonBody(request),
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(response.error_code.as_deref(), Some("INVALID_HEX"));
+        }
+    }
+
+    #[test]
+    fn test_merkle_proof_depth_limit() {
+        let siblings = |depth: usize| (0..depth).map(|i| format!("{:064x}", i + 1)).collect();
+        let mut request = sample_request();
+        request.merkle = siblings(16);
+        assert_eq!(request.merkle_proof().unwrap().0.len(), 16);
+
+        request.merkle = siblings(25);
+        let err = request.merkle_proof().unwrap_err();
+        assert!(matches!(err, ProofError::InvalidMerkleSiblings(_)));
+        assert!(err.to_string().contains("maximum tree depth"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_prove_rejects_self_referential_siblings() {
+        // The transaction's own hash as its left-hand sibling (position 1465 is odd)
+        let mut request = sample_request();
+        request.merkle[0] = request.tx_hash.to_uppercase();
+        let (status, Json(response)) = generate_bitcoin_proof(
+            State(mock_state(Vec::new())),
+            Query(FieldsQuery::default()),
+            JsonBody(request),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.error_code.as_deref(),
+            Some("INVALID_MERKLE_SIBLINGS")
+        );
+        assert!(response.error.unwrap().contains("own hash"));
+
+        // Repeated adjacent siblings
+        let mut request = sample_request();
+        request.merkle[3] = request.merkle[2].clone();
+        let err = request.merkle_proof().unwrap_err();
+        assert!(err.to_string().contains("siblings 2 and 3"), "{}", err);
+
+        // The duplicated last leaf on the right is legitimate
+        assert!(validate_merkle_siblings("ab", &["AB".to_string()], 0).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_prove_hints_at_reversed_siblings() {
+        // Siblings already in internal byte order (reversed a second time)
+        let mut request = sample_request();
+        request.merkle = siblings_from_hex(&request.merkle)
+            .unwrap()
+            .iter()
+            .map(hex::encode)
+            .collect();
+        request.check_siblings = true;
+
+        let (status, Json(response)) = generate_bitcoin_proof(
+            State(mock_state(Vec::new())),
+            Query(FieldsQuery::default()),
+            JsonBody(request),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.error_code.as_deref(),
+            Some("INVALID_MERKLE_SIBLINGS")
+        );
+        let error = response.error.unwrap();
+        assert!(error.contains("internal byte order"), "{}", error);
+
+        // Correct siblings pass the check and fail later on the target address
+        let mut request = sample_request();
+        request.check_siblings = true;
+        assert!(matches!(
+            prepare_proof(&request, TARGET_ADDRESS),
+            Err(ProofError::ValidationFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_estimate_reports_cycles() {
+        // Stand-in executor whose cycle count depends only on the zkVM input
+        let executor = |stdin: SP1Stdin| {
+            Ok(stdin
+                .buffer
+                .iter()
+                .map(|item| item.len() as u64 * 100)
+                .sum())
+        };
+
+        let Json(first) = estimate_with(&sample_request(), TARGET_ADDRESS, executor)
+            .await
+            .unwrap();
+        let Json(second) = estimate_with(&sample_request(), TARGET_ADDRESS, executor)
+            .await
+            .unwrap();
+        assert!(first.valid);
+        assert!(first.cycles.unwrap() > 0);
+        assert_eq!(first.cycles, second.cycles);
+
+        // An aborted execution is reported as invalid, without cycles
+        let Json(aborted) = estimate_with(&sample_request(), TARGET_ADDRESS, |_| {
+            Err(anyhow::anyhow!("Execution failed: guest panicked"))
+        })
+        .await
+        .unwrap();
+        assert!(!aborted.valid);
+        assert_eq!(aborted.cycles, None);
+        assert_eq!(
+            aborted.error.as_deref(),
+            Some("Execution failed: guest panicked")
+        );
+
+        // Malformed requests are rejected before executing
+        let mut request = sample_request();
+        request.tx_hash = "00".repeat(32);
+        let (status, _) = estimate_with(&request, TARGET_ADDRESS, |_| unreachable!())
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_public_values_decode_committed_fields() {
+        // Stand-in executor committing what the program commits for the fixture
+        let executor = |_: SP1Stdin| Ok(PublicValuesStruct::abi_encode(&sample_public_values()));
+
+        let Json(response) = public_values_with(&sample_request(), TARGET_ADDRESS, executor)
+            .await
+            .unwrap();
+        assert!(response.valid);
+        let bytes = hex::decode(response.public_values.unwrap()).unwrap();
+        let decoded = decode_public_values(&bytes).unwrap();
+        assert_eq!(response.decoded.as_ref(), Some(&decoded));
+        assert_eq!(decoded.block_hash, fixtures::BLOCK_HASH);
+        assert_eq!(decoded.total_amount, 1_240_000_000);
+        assert_eq!(decoded.txid, fixtures::TXID);
+        assert_eq!(decoded.output_index, sample_public_values().outputIndex);
+        assert_eq!(decoded.target_address, fixtures::TARGET_ADDRESS);
+        assert_eq!(decoded.op_return_bytes, None);
+        assert_eq!(decoded.expected_op_return, None);
+        assert_eq!(
+            decoded.modes(),
+            sample_request().modes(fixtures::TARGET_ADDRESS, None)
+        );
+
+        // Every mode's flag and value has a fixed slot, so the layout never depends on
+        // the request
+        let mut values = sample_public_values();
+        values.opReturnBytesCommitted = true;
+        values.blockHashPinned = true;
+        values.opReturnChecked = true;
+        values.expectedOpReturn = [0xcd; 32].into();
+        values.opReturnAnchored = true;
+        let decoded = decode_public_values(&PublicValuesStruct::abi_encode(&values)).unwrap();
+        assert_eq!(decoded.op_return_bytes, Some(0));
+        assert!(decoded.block_hash_pinned);
+        assert_eq!(decoded.expected_op_return, Some("cd".repeat(32)));
+        assert!(decoded.op_return_anchored);
+        assert!(parse_op_return_commitment(Some("cd")).is_err());
+
+        // Truncated or padded bytes do not decode
+        assert!(decode_public_values(&bytes[..bytes.len() - 1]).is_err());
+        let mut padded = bytes.clone();
+        padded.extend_from_slice(&[0; 32]);
+        assert!(decode_public_values(&padded).is_err());
+
+        // A tampered request aborts execution: invalid, nothing committed
+        let mut tampered = sample_request();
+        tampered.block_header.replace_range(72..74, "00");
+        let Json(response) = public_values_with(&tampered, TARGET_ADDRESS, |_| {
+            Err(anyhow::anyhow!("Execution failed: guest panicked"))
+        })
+        .await
+        .unwrap();
+        assert!(!response.valid);
+        assert_eq!(response.public_values, None);
+        assert_eq!(response.decoded, None);
+    }
+
+    #[test]
+    fn test_committed_values_checked_against_native_verification() {
+        type Tamper = dyn Fn(&mut PublicValuesStruct);
+        let request = sample_request();
+        let (_, verification, expected) =
+            prepare_proof(&request, fixtures::TARGET_ADDRESS).unwrap();
+        let response = |tamper: &Tamper| {
+            let mut committed = sample_public_values();
+            tamper(&mut committed);
+            ProofResponse {
+                success: true,
+                error: None,
+                error_code: None,
+                public_values: Some(PublicValuesStruct::abi_encode(&committed)),
+                proof_bytes: None,
+                execution_time_ms: None,
+                verification: None,
+                batch: None,
+                request_id: None,
+            }
+        };
+        let check = |response: &ProofResponse| {
+            check_committed_values(response, &request.tx_hash, &verification, &expected)
+        };
+
+        check(&response(&|_| {})).unwrap();
+
+        // A proof committing a different amount than the transaction pays is caught
+        let error = check(&response(&|v| v.totalAmount += 1)).unwrap_err();
+        assert_eq!(error.code(), "PUBLIC_VALUES_MISMATCH");
+        assert!(error.to_string().contains("total_amount"), "{}", error);
+
+        // So is a committed txid other than the requested one
+        let other_txid = "00".repeat(32);
+        let error =
+            check_committed_values(&response(&|_| {}), &other_txid, &verification, &expected)
+                .unwrap_err();
+        assert!(error.to_string().contains("txid"), "{}", error);
+
+        // And every other committed value and mode flag
+        let tampered: [(&str, &Tamper); 7] = [
+            ("output_index", &|v| v.outputIndex += 1),
+            ("op_return_bytes", &|v| v.opReturnBytes = 1),
+            ("all_inputs_confirmed", &|v| v.allInputsConfirmed = true),
+            ("funding_block_hashes", &|v| {
+                v.fundingBlockHashes = vec![fixtures::BLOCK_HASH.to_string()]
+            }),
+            ("op_return_anchored", &|v| v.opReturnAnchored = true),
+            ("modes", &|v| v.blockHashPinned = true),
+            ("modes", &|v| v.targetAddress = TARGET_ADDRESS.to_string()),
+        ];
+        for (field, tamper) in tampered {
+            let error = check(&response(tamper)).unwrap_err();
+            assert!(error.to_string().contains(field), "{}: {}", field, error);
+        }
+
+        // Requested modes are compared value by value
+        let mut request = sample_request();
+        request.commit_op_return_bytes = true;
+        let (_, verification, expected) =
+            prepare_proof(&request, fixtures::TARGET_ADDRESS).unwrap();
+        assert_eq!(expected.op_return_bytes, Some(0));
+        let committed = response(&|v| {
+            v.opReturnBytesCommitted = true;
+            v.opReturnBytes = 3;
+        });
+        let error = check_committed_values(&committed, &request.tx_hash, &verification, &expected)
+            .unwrap_err();
+        assert!(error.to_string().contains("op_return_bytes"), "{}", error);
+    }
+
+    /// Events of a proof stream for `sample_request` whose prover commits `committed`,
+    /// as (event, data) pairs
+    async fn sample_proof_stream(
+        committed: anyhow::Result<Vec<u8>>,
+        stats: &Arc<ProofStats>,
+    ) -> Vec<(String, String)> {
+        let request = sample_request();
+        let (_, verification, expected) =
+            prepare_proof(&request, fixtures::TARGET_ADDRESS).unwrap();
+        // Fake prover walking through every phase
+        let fake_prover = move |on_phase: &dyn Fn(ProvePhase), _: &AtomicBool| {
+            on_phase(ProvePhase::SetupComplete);
+            on_phase(ProvePhase::Proving);
+            on_phase(ProvePhase::Verifying);
+            committed
+        };
+
+        let sse = proof_event_stream(
+            fake_prover,
+            (),
+            stats.clone(),
+            request.tx_hash,
+            verification,
+            expected,
+            std::time::Instant::now(),
+        );
+        let body = axum::response::IntoResponse::into_response(sse).into_body();
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        text.split("\n\n")
+            .filter_map(|event| {
+                let mut lines = event.lines();
+                let name = lines.next()?.strip_prefix("event: ")?;
+                let data = lines.next()?.strip_prefix("data: ")?;
+                Some((name.to_string(), data.to_string()))
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_proof_stream_emits_phases_and_done() {
+        let committed = PublicValuesStruct::abi_encode(&sample_public_values());
+        let stats = Arc::default();
+        let events = sample_proof_stream(Ok(committed.clone()), &stats).await;
+        let names: Vec<&str> = events.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["setup_complete", "proving", "verifying", "done"]);
+
+        // The terminal event carries the public values
+        let response: serde_json::Value = serde_json::from_str(&events[3].1).unwrap();
+        assert_eq!(response["success"], true);
+        assert_eq!(response["public_values"], serde_json::json!(committed));
+        assert_eq!(response["verification"]["total_amount"], 1240000000u64);
+        assert_eq!(response["verification"]["total_amount_btc"], "12.40000000");
+        assert_eq!((stats.total(), stats.success_rate()), (1, 1.0));
+
+        // A failed proof is recorded too
+        let events = sample_proof_stream(Err(anyhow::anyhow!("guest panicked")), &stats).await;
+        assert_eq!(events.last().unwrap().0, "error");
+        assert_eq!((stats.total(), stats.success_rate()), (2, 0.5));
+    }
+
+    #[tokio::test]
+    async fn test_proof_stream_rejects_mismatched_public_values() {
+        // A proof committing another amount than native verification computed
+        let mut tampered = sample_public_values();
+        tampered.totalAmount += 1;
+        let stats = Arc::default();
+        let events =
+            sample_proof_stream(Ok(PublicValuesStruct::abi_encode(&tampered)), &stats).await;
+        let names: Vec<&str> = events.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["setup_complete", "proving", "verifying", "error"]);
+
+        let response: serde_json::Value = serde_json::from_str(&events[3].1).unwrap();
+        assert_eq!(response["success"], false);
+        assert_eq!(response["error_code"], "PUBLIC_VALUES_MISMATCH");
+        assert!(response["error"].as_str().unwrap().contains("total_amount"));
+        assert_eq!(response["public_values"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_batch_rejects_empty_and_malformed_header() {
+        let request = sample_request();
+        let claim = TxClaim {
+            tx_hex: request.tx,
+            txid: request.tx_hash,
+            merkle_siblings: request.merkle,
+            pos: request.position,
+        };
+
+        let empty = BatchProofRequest {
+            input: BatchProofInput {
+                block_header: request.block_header.clone(),
+                claims: vec![],
+            },
+            system: ProofSystem::default(),
+        };
+        let (status, Json(response)) =
+            generate_batch_proof(State(mock_state(Vec::new())), JsonBody(empty))
+                .await
+                .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(response.error.unwrap().contains("no claims"));
+
+        let malformed = BatchProofRequest {
+            input: BatchProofInput {
+                block_header: "00".repeat(79),
+                claims: vec![claim],
+            },
+            system: ProofSystem::default(),
+        };
+        let (status, Json(response)) =
+            generate_batch_proof(State(mock_state(Vec::new())), JsonBody(malformed))
+                .await
+                .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(response.batch.is_none());
+    }
+
+    #[test]
+    fn test_batch_program_commits_per_claim_results() {
+        // One claim for the block 363348 fixture and one whose txid does not match it
+        let request = sample_request();
+        let valid = TxClaim {
+            tx_hex: request.tx,
+            txid: request.tx_hash,
+            merkle_siblings: request.merkle,
+            pos: request.position,
+        };
+        let invalid = TxClaim {
+            txid: "00".repeat(32),
+            ..valid.clone()
+        };
+        let input = BatchProofInput {
+            block_header: request.block_header,
+            claims: vec![valid, invalid],
+        };
+
+        // Run the batch ELF without proving and read back what it committed
+        let mut stdin = SP1Stdin::new();
+        stdin.write(&input);
+        stdin.write(&fixtures::TARGET_ADDRESS.to_string());
+        let (committed, _) = ProverBackend::local()
+            .execute(BATCH_PROOF_ELF, &stdin)
+            .unwrap();
+        assert_eq!(
+            decode_batch_public_values(&committed).unwrap(),
+            DecodedBatchValues {
+                block_hash: fixtures::BLOCK_HASH.to_string(),
+                target_address: fixtures::TARGET_ADDRESS.to_string(),
+                all_valid: false,
+                results: vec![(fixtures::TXID.to_string(), true), ("00".repeat(32), false),],
+                total_amount: 1240000000,
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_batch_public_values() {
+        let claim_txid = hex::decode(fixtures::TXID).unwrap();
+        let mut txid = [0u8; 32];
+        txid.copy_from_slice(&claim_txid);
+        txid.reverse();
+        let values = BatchPublicValuesStruct {
+            blockHash: fixtures::BLOCK_HASH.to_string(),
+            targetAddress: TARGET_ADDRESS.to_string(),
+            allValid: false,
+            txids: vec![txid.into(), [0u8; 32].into()],
+            results: vec![true, false],
+            totalAmount: 1240000000,
+        };
+        let encoded = BatchPublicValuesStruct::abi_encode(&values);
+        assert_eq!(
+            decode_batch_public_values(&encoded).unwrap(),
+            DecodedBatchValues {
+                block_hash: fixtures::BLOCK_HASH.to_string(),
+                target_address: TARGET_ADDRESS.to_string(),
+                all_valid: false,
+                results: vec![(fixtures::TXID.to_string(), true), ("00".repeat(32), false)],
+                total_amount: 1240000000,
+            }
+        );
+
+        // Trailing bytes and a result missing for a txid are rejected
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert!(decode_batch_public_values(&trailing).is_err());
+        let missing_result = BatchPublicValuesStruct {
+            results: vec![true],
+            ..values
+        };
+        assert!(
+            decode_batch_public_values(&BatchPublicValuesStruct::abi_encode(&missing_result))
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_prover_readiness() {
+        let state = mock_state(Vec::new());
+
+        let (status, Json(health)) = health_check(State(state.clone())).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(health.status, "starting");
+
+        // Proving is refused until setup finished
+        let (status, _) = prove_and_respond(
+            &state,
+            single_program,
+            ProofSystem::default(),
+            SP1Stdin::new(),
+            std::time::Instant::now(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        state.run_setup().unwrap();
+        let (status, Json(health)) = health_check(State(state)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(health.status, "ready");
+        assert_eq!(health.elf_size, BITCOIN_PROOF_ELF.len());
+        assert_eq!(health.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(health.prover_mode, "local");
+        assert_eq!(health.total_proofs, 0);
+        assert_eq!(health.last_proof_ms, None);
+    }
+
+    #[test]
+    fn test_verify_after_prove_can_be_skipped() {
+        use std::cell::RefCell;
+
+        let mut committed = SP1PublicValues::new();
+        committed.write(&"00".repeat(32));
+        committed.write(&1240000000u64);
+        let state = AppState::new(MockProver::new(committed.to_vec()));
+        state.run_setup().unwrap();
+        let setup = state.prover().unwrap();
+        let stdin = SP1Stdin::new();
+
+        for verify in [true, false] {
+            let prover = MockProver::new(committed.to_vec());
+            let phases = RefCell::new(Vec::new());
+            let on_phase = |phase| phases.borrow_mut().push(phase);
+            let public_values = prove_with_backend(
+                &prover,
+                &setup.single,
+                ProofSystem::default(),
+                &stdin,
+                verify,
+                &on_phase,
+                &AtomicBool::new(false),
+            )
+            .unwrap();
+            assert_eq!(public_values, committed.to_vec());
+
+            let verified = phases.into_inner().contains(&ProvePhase::Verifying);
+            if verify {
+                assert_eq!(prover.calls(), ["prove", "verify"]);
+                assert!(verified);
+            } else {
+                assert_eq!(prover.calls(), ["prove"]);
+                assert!(!verified);
+            }
+        }
+
+        // A job cancelled by a timeout never reaches the prover
+        let prover = MockProver::new(committed.to_vec());
+        let err = prove_with_backend(
+            &prover,
+            &setup.single,
+            ProofSystem::default(),
+            &stdin,
+            true,
+            &|_| {},
+            &AtomicBool::new(true),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("cancelled"), "{}", err);
+        assert!(prover.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_proof_job_never_completes() {
+        let proven = PublicValuesStruct::abi_encode(&sample_public_values());
+        let state = mock_state(proven)
+            .with_target_address(fixtures::TARGET_ADDRESS)
+            .with_limiter(ProofLimiter::new(1, 1));
+        state.run_setup().unwrap();
+        let status_of = |job_id: &str| {
+            proof_job_status(
+                State(state.clone()),
+                Query(JobQuery {
+                    job_id: job_id.to_string(),
+                }),
+            )
+        };
+
+        // Hold the only proof slot so the job stays queued
+        let slot = state.acquire_proof_slot().await.unwrap();
+        let (status, Json(job)) =
+            submit_proof_job(State(state.clone()), JsonBody(sample_request()))
+                .await
+                .unwrap();
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(job.status, JobStatus::Pending);
+
+        let Json(cancelled) = cancel_proof_job(State(state.clone()), Path(job.job_id.clone()))
+            .await
+            .unwrap();
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+
+        // Freeing the slot does not revive it: the prover is never called
+        drop(slot);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let Json(polled) = status_of(&job.job_id).await.unwrap();
+        assert_eq!(polled.status, JobStatus::Cancelled);
+        assert_eq!(polled.result, None);
+        assert!(state.client().calls().is_empty());
+        assert_eq!(state.proof_stats().total(), 0);
+
+        // A finished job reports its result, and cancelling it leaves it done
+        let (_, Json(job)) = submit_proof_job(State(state.clone()), JsonBody(sample_request()))
+            .await
+            .unwrap();
+        let mut polled = status_of(&job.job_id).await.unwrap().0;
+        for _ in 0..100 {
+            if polled.status != JobStatus::Pending {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            polled = status_of(&job.job_id).await.unwrap().0;
+        }
+        assert_eq!(polled.status, JobStatus::Done);
+        let result = polled.result.unwrap();
+        assert_eq!(result["success"], true);
+        assert_eq!(result["verification"]["total_amount"], 1240000000u64);
+        let Json(after) = cancel_proof_job(State(state.clone()), Path(job.job_id))
+            .await
+            .unwrap();
+        assert_eq!(after.status, JobStatus::Done);
+
+        // Unknown jobs are not found
+        let (status, Json(response)) = status_of("no-such-job").await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(response.error_code.as_deref(), Some("JOB_NOT_FOUND"));
+        let (status, _) = cancel_proof_job(State(state), Path("no-such-job".to_string()))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_handlers_with_mock_prover() {
+        let mut committed = SP1PublicValues::new();
+        committed.write(&"00".repeat(32));
+        committed.write(&1240000000u64);
+        let state = AppState::new(MockProver::new(committed.to_vec()));
+        state.run_setup().unwrap();
+
+        // A request failing native verification never reaches the prover
+        let (status, Json(response)) = generate_bitcoin_proof(
+            State(state.clone()),
+            Query(FieldsQuery::default()),
+            JsonBody(sample_request()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(response.error_code.as_deref(), Some("VALIDATION_FAILED"));
+        assert!(state.client().calls().is_empty());
+
+        // A request paying the server's target is proven, with the committed values checked
+        let proven = PublicValuesStruct::abi_encode(&sample_public_values());
+        let state = mock_state(proven.clone()).with_target_address(fixtures::TARGET_ADDRESS);
+        state.run_setup().unwrap();
+        let Json(response) = generate_bitcoin_proof(
+            State(state.clone()),
+            Query(FieldsQuery::default()),
+            JsonBody(sample_request()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response["success"], true);
+        assert_eq!(response["verification"]["total_amount"], 1240000000u64);
+        assert_eq!(response["public_values"], serde_json::json!(proven));
+        assert_eq!(state.client().calls(), ["prove", "verify"]);
+
+        // A batch is proven even with invalid claims (the fixture does not pay the
+        // server's target), returning the mock's public values
+        let request = sample_request();
+        let batch = BatchProofRequest {
+            input: BatchProofInput {
+                block_header: request.block_header,
+                claims: vec![TxClaim {
+                    tx_hex: request.tx,
+                    txid: request.tx_hash.clone(),
+                    merkle_siblings: request.merkle,
+                    pos: request.position,
+                }],
+            },
+            system: ProofSystem::Plonk,
+        };
+        let batch_committed = BatchPublicValuesStruct::abi_encode(&BatchPublicValuesStruct {
+            blockHash: fixtures::BLOCK_HASH.to_string(),
+            targetAddress: TARGET_ADDRESS.to_string(),
+            allValid: false,
+            txids: vec![batch.input.claims[0].txid_bytes().into()],
+            results: vec![false],
+            totalAmount: 0,
+        });
+        let state = AppState::new(MockProver::new(batch_committed.clone()));
+        state.run_setup().unwrap();
+        let Json(response) = generate_batch_proof(State(state.clone()), JsonBody(batch.clone()))
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(response.public_values, Some(batch_committed));
+        assert_eq!(response.batch.unwrap().results, vec![false]);
+        assert_eq!(state.client().calls(), ["prove", "verify"]);
+        assert_eq!(state.client().modes(), [SP1ProofMode::Plonk]);
+        assert_eq!(state.proof_stats().success_rate(), 1.0);
+
+        // Public values that disagree with native verification are a server error
+        let state = AppState::new(MockProver::new(committed.to_vec()));
+        state.run_setup().unwrap();
+        let (status, Json(response)) =
+            generate_batch_proof(State(state.clone()), JsonBody(batch.clone()))
+                .await
+                .unwrap_err();
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(response.error_code.as_deref(), Some("DECODE_ERROR"));
+
+        // A proving failure is reported in the body
+        let state = AppState::new(MockProver::failing("guest panicked"));
+        state.run_setup().unwrap();
+        let Json(response) = generate_batch_proof(State(state.clone()), JsonBody(batch))
+            .await
+            .unwrap();
+        assert!(!response.success);
+        assert_eq!(
+            response.error_code.as_deref(),
+            Some("PROOF_GENERATION_FAILED")
+        );
+        assert_eq!(state.client().calls(), ["prove"]);
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_proof_stats() {
+        let state = AppState::new(MockProver::failing("guest panicked"));
+        state.run_setup().unwrap();
+
+        // A failed proof attempt
+        let Json(response) = prove_and_respond(
+            &state,
+            single_program,
+            ProofSystem::default(),
+            SP1Stdin::new(),
+            std::time::Instant::now(),
+        )
+        .await
+        .unwrap();
+        assert!(!response.success);
+        state.proof_stats().record(true, 1200);
+
+        let (_, Json(health)) = health_check(State(state)).await;
+        assert_eq!(health.total_proofs, 2);
+        assert_eq!(health.success_rate, 0.5);
+        assert_eq!(health.last_proof_ms, Some(1200));
+    }
+
+    #[tokio::test]
+    async fn test_prove_rejected_when_proof_queue_full() {
+        let state = mock_state(Vec::new()).with_limiter(ProofLimiter::new(1, 0));
+        state.run_setup().unwrap();
+
+        // One proof already holds the only slot and nothing may queue behind it
+        let _running = state.acquire_proof_slot().await.unwrap();
+        let (status, Json(response)) = prove_and_respond(
+            &state,
+            single_program,
+            ProofSystem::default(),
+            SP1Stdin::new(),
+            std::time::Instant::now(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.error_code.as_deref(), Some("PROVER_BUSY"));
+    }
+
+    #[tokio::test]
+    async fn test_vkey_returns_bytes32_digest() {
+        let state = mock_state(Vec::new());
+        let (status, _) = get_vkey(State(state.clone())).await.unwrap_err();
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        state.run_setup().unwrap();
+        let Json(response) = get_vkey(State(state.clone())).await.unwrap();
+        assert_eq!(response.vkey, state.prover().unwrap().single.vkey_digest());
+        assert_eq!(response.vkey.len(), 66);
+        assert!(response.vkey.starts_with("0x"));
+        assert!(response.vkey[2..].chars().all(|c| c.is_ascii_hexdigit()));
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tokio::task::AbortHandle;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// State of a background proof job
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Queued or proving
+    Pending,
+    /// Finished with a successful proof
+    Done,
+    /// Finished without a proof
+    Failed,
+    /// Cancelled before it finished
+    Cancelled,
+}
+
+/// A job's id, state and, once finished, its proof response
+#[derive(Serialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct JobResponse {
+    pub job_id: String,
+    pub status: JobStatus,
+    /// The `/prove` response body, once the job is done or failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub result: Option<serde_json::Value>,
+}
+
+struct Job {
+    status: JobStatus,
+    result: Option<serde_json::Value>,
+    /// Task running the job, once spawned
+    task: Option<AbortHandle>,
+}
+
+/// Proof jobs by id; finished jobs are kept so their result can be polled
+#[derive(Clone, Default)]
+pub struct JobRegistry {
+    jobs: Arc<Mutex<HashMap<String, Job>>>,
+}
+
+impl JobRegistry {
+    /// Register a new pending job and return its id
+    pub fn create(&self) -> String {
+        let job_id = Uuid::new_v4().to_string();
+        self.lock().insert(
+            job_id.clone(),
+            Job {
+                status: JobStatus::Pending,
+                result: None,
+                task: None,
+            },
+        );
+        job_id
+    }
+
+    /// Record the task running `job_id`, aborting it if the job was cancelled meanwhile
+    pub fn attach(&self, job_id: &str, task: AbortHandle) {
+        let mut jobs = self.lock();
+        match jobs.get_mut(job_id) {
+            Some(job) if job.status == JobStatus::Pending => job.task = Some(task),
+            _ => task.abort(),
+        }
+    }
+
+    /// Store a pending job's result; a cancelled job keeps its state
+    pub fn finish(&self, job_id: &str, success: bool, result: serde_json::Value) {
+        if let Some(job) = self.lock().get_mut(job_id) {
+            if job.status == JobStatus::Pending {
+                job.status = match success {
+                    true => JobStatus::Done,
+                    false => JobStatus::Failed,
+                };
+                job.result = Some(result);
+                job.task = None;
+            }
+        }
+    }
+
+    /// Cancel `job_id` if still pending, aborting its task; returns the job's state after,
+    /// or None for an unknown id
+    pub fn cancel(&self, job_id: &str) -> Option<JobResponse> {
+        let mut jobs = self.lock();
+        let job = jobs.get_mut(job_id)?;
+        if job.status == JobStatus::Pending {
+            if let Some(task) = job.task.take() {
+                task.abort();
+            }
+            job.status = JobStatus::Cancelled;
+        }
+        Some(Self::response(job_id, job))
+    }
+
+    /// State of `job_id`, or None for an unknown id
+    pub fn get(&self, job_id: &str) -> Option<JobResponse> {
+        self.lock()
+            .get(job_id)
+            .map(|job| Self::response(job_id, job))
+    }
+
+    fn response(job_id: &str, job: &Job) -> JobResponse {
+        JobResponse {
+            job_id: job_id.to_string(),
+            status: job.status,
+            result: job.result.clone(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Job>> {
+        // A panic while holding the lock leaves the map itself consistent
+        self.jobs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,137 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::Response,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Default number of requests allowed to wait for a proof slot
+const DEFAULT_MAX_QUEUED_PROOFS: usize = 8;
+
+/// Seconds clients are asked to wait before retrying a 503
+const RETRY_AFTER_SECS: u64 = 30;
+
+/// Bounds concurrent proof generation: at most `max_concurrent` proofs run at once,
+/// and at most `max_queued` further requests wait for a slot
+#[derive(Clone)]
+pub struct ProofLimiter {
+    slots: Arc<Semaphore>,
+    queued: Arc<AtomicUsize>,
+    max_queued: usize,
+}
+
+impl ProofLimiter {
+    pub fn new(max_concurrent: usize, max_queued: usize) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            queued: Arc::new(AtomicUsize::new(0)),
+            max_queued,
+        }
+    }
+
+    /// Limits from `MAX_CONCURRENT_PROOFS` (default: logical CPUs / 4, at least 1)
+    /// and `MAX_QUEUED_PROOFS` (default 8)
+    pub fn from_env() -> Self {
+        let default_concurrent = std::thread::available_parallelism()
+            .map_or(1, |cpus| cpus.get() / 4)
+            .max(1);
+        Self::new(
+            env_usize("MAX_CONCURRENT_PROOFS").unwrap_or(default_concurrent),
+            env_usize("MAX_QUEUED_PROOFS").unwrap_or(DEFAULT_MAX_QUEUED_PROOFS),
+        )
+    }
+
+    /// Wait for a proof slot, or None when the queue is already full
+    /// The slot is released when the returned permit is dropped
+    pub async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        if let Ok(permit) = self.slots.clone().try_acquire_owned() {
+            return Some(permit);
+        }
+
+        let reserved = self
+            .queued
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| {
+                (queued < self.max_queued).then_some(queued + 1)
+            });
+        if reserved.is_err() {
+            return None;
+        }
+        let permit = self.slots.clone().acquire_owned().await;
+        self.queued.fetch_sub(1, Ordering::SeqCst);
+        // The semaphore is never closed
+        permit.ok()
+    }
+}
+
+impl Default for ProofLimiter {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+fn env_usize(name: &str) -> Option<usize> {
+    std::env::var(name).ok()?.trim().parse().ok()
+}
+
+/// Add `Retry-After` to 503 responses (prover still starting or proof queue full)
+pub async fn retry_after_on_unavailable(mut response: Response) -> Response {
+    if response.status() == StatusCode::SERVICE_UNAVAILABLE {
+        response
+            .headers_mut()
+            .entry(header::RETRY_AFTER)
+            .or_insert(HeaderValue::from(RETRY_AFTER_SECS));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_limiter_queues_then_rejects() {
+        let limiter = ProofLimiter::new(1, 1);
+        let running = limiter.acquire().await.unwrap();
+
+        // The second request waits for the running proof
+        let waiting = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire().await.is_some() }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+
+        // The queue holds one request, so a third is rejected
+        assert!(limiter.acquire().await.is_none());
+
+        drop(running);
+        assert!(tokio::time::timeout(Duration::from_secs(5), waiting)
+            .await
+            .unwrap()
+            .unwrap());
+
+        // Without a queue the second concurrent request is rejected immediately
+        let limiter = ProofLimiter::new(1, 0);
+        let _running = limiter.acquire().await.unwrap();
+        assert!(limiter.acquire().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_only_on_503() {
+        let unavailable = Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .body(Default::default())
+            .unwrap();
+        let response = retry_after_on_unavailable(unavailable).await;
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+
+        let ok = Response::new(Default::default());
+        let response = retry_after_on_unavailable(ok).await;
+        assert!(response.headers().get(header::RETRY_AFTER).is_none());
+    }
+}
@@ -1 +1,9 @@
+pub mod esplora;
+pub mod extract;
 pub mod handlers;
+pub mod jobs;
+pub mod limits;
+pub mod openapi;
+pub mod prover;
+pub mod request_id;
+pub mod state;
@@ -0,0 +1,111 @@
+use axum::Json;
+use utoipa::OpenApi;
+
+use crate::server::handlers::{
+    BatchProofRequest, DecodedPublicValues, EstimateResponse, HealthResponse, ProofRequest,
+    ProofResponse, ProveByTxidRequest, PublicValuesResponse, VkeyResponse,
+};
+use crate::server::jobs::{JobResponse, JobStatus};
+
+/// OpenAPI description of the proof server
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "zk-bitcoin-light-client"),
+    paths(
+        crate::server::handlers::health_check,
+        crate::server::handlers::generate_bitcoin_proof,
+        crate::server::handlers::generate_bitcoin_proof_by_txid,
+        crate::server::handlers::generate_batch_proof,
+        crate::server::handlers::stream_bitcoin_proof,
+        crate::server::handlers::submit_proof_job,
+        crate::server::handlers::proof_job_status,
+        crate::server::handlers::cancel_proof_job,
+        crate::server::handlers::verify_offchain,
+        crate::server::handlers::estimate_cycles,
+        crate::server::handlers::public_values,
+        crate::server::handlers::get_vkey,
+    ),
+    components(schemas(
+        ProofRequest,
+        ProofResponse,
+        HealthResponse,
+        ProveByTxidRequest,
+        BatchProofRequest,
+        VkeyResponse,
+        EstimateResponse,
+        PublicValuesResponse,
+        DecodedPublicValues,
+        JobResponse,
+        JobStatus,
+    ))
+)]
+pub struct ApiDoc;
+
+/// Serve the OpenAPI spec for the request and response types
+pub async fn openapi_spec() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+
+    #[tokio::test]
+    async fn test_openapi_spec_describes_proof_request() {
+        let app = Router::new().route("/openapi.json", get(openapi_spec));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let spec: serde_json::Value = reqwest::get(format!("http://{}/openapi.json", addr))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let request = &spec["components"]["schemas"]["ProofRequest"];
+        assert_eq!(request["properties"]["merkle"]["type"], "array");
+        assert_eq!(request["properties"]["merkle"]["items"]["type"], "string");
+        let required = request["required"].as_array().unwrap();
+        assert!(required.contains(&"tx".into()));
+        assert!(!required.contains(&"merkle".into()));
+        assert!(spec["paths"]["/prove"]["post"].is_object());
+        assert!(spec["components"]["schemas"]["HealthResponse"].is_object());
+
+        // Every route but the spec itself is described
+        for (path, method) in [
+            ("/health", "get"),
+            ("/prove/by-txid", "post"),
+            ("/prove/batch", "post"),
+            ("/prove/stream", "get"),
+            ("/prove/async", "post"),
+            ("/prove/status", "get"),
+            ("/prove/{job_id}", "delete"),
+            ("/verify-offchain", "post"),
+            ("/estimate", "post"),
+            ("/public-values", "post"),
+            ("/vkey", "get"),
+        ] {
+            assert!(
+                spec["paths"][path][method].is_object(),
+                "{} {}",
+                method,
+                path
+            );
+        }
+        let by_txid = &spec["components"]["schemas"]["ProveByTxidRequest"];
+        assert!(by_txid["required"]
+            .as_array()
+            .unwrap()
+            .contains(&"txid".into()));
+        let job = &spec["components"]["schemas"]["JobResponse"];
+        assert_eq!(
+            job["properties"]["status"]["$ref"],
+            "#/components/schemas/JobStatus"
+        );
+        let status = &spec["paths"]["/prove/status"]["get"];
+        assert_eq!(status["parameters"][0]["name"], "job_id");
+        assert_eq!(status["parameters"][0]["in"], "query");
+    }
+}
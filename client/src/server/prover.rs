@@ -0,0 +1,467 @@
+use std::time::Duration;
+
+use sp1_sdk::{
+    CpuProver, HashableKey, NetworkProver, Prover as _, ProverClient, SP1ProofMode,
+    SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin, SP1VerificationError, SP1VerifyingKey,
+};
+use tracing::warn;
+
+/// Env var holding the Succinct prover network key, required in network mode
+const NETWORK_PRIVATE_KEY: &str = "NETWORK_PRIVATE_KEY";
+
+/// Default attempts per network proof, including the first
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// Default wait before the first retry; doubles after each failed attempt
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Where proofs are generated, from `PROVER_MODE`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProverMode {
+    /// Prove on this machine's CPU
+    #[default]
+    Local,
+    /// Delegate proving to the Succinct prover network
+    Network,
+}
+
+impl ProverMode {
+    /// Parse a `PROVER_MODE` value: `local` (default when unset) or `network`
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("local") => Ok(ProverMode::Local),
+            Some("network") => Ok(ProverMode::Network),
+            Some(other) => Err(format!(
+                "invalid PROVER_MODE {:?}: expected local or network",
+                other
+            )),
+        }
+    }
+
+    /// Mode from `PROVER_MODE`; network mode also needs `NETWORK_PRIVATE_KEY`
+    pub fn from_env() -> Result<Self, String> {
+        let mode = Self::parse(std::env::var("PROVER_MODE").ok().as_deref())?;
+        if mode == ProverMode::Network && std::env::var(NETWORK_PRIVATE_KEY).is_err() {
+            return Err(format!("PROVER_MODE=network needs {}", NETWORK_PRIVATE_KEY));
+        }
+        Ok(mode)
+    }
+
+    /// Name as reported by `/health`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProverMode::Local => "local",
+            ProverMode::Network => "network",
+        }
+    }
+
+    /// Build the prover client for this mode
+    pub fn client(self) -> Result<ProverBackend, String> {
+        ProverBackend::build(self, std::env::var(NETWORK_PRIVATE_KEY).ok())
+    }
+}
+
+/// SP1 prover client for the configured mode
+pub enum ProverBackend {
+    Local(CpuProver),
+    /// Network proving retries transient failures
+    Network(NetworkProver, RetryPolicy),
+}
+
+impl ProverBackend {
+    /// Local CPU prover, the default backend
+    pub fn local() -> Self {
+        ProverBackend::Local(ProverClient::builder().cpu().build())
+    }
+
+    fn build(mode: ProverMode, network_key: Option<String>) -> Result<Self, String> {
+        match mode {
+            ProverMode::Local => Ok(ProverBackend::local()),
+            ProverMode::Network => {
+                let key = network_key
+                    .ok_or_else(|| format!("network prover needs {}", NETWORK_PRIVATE_KEY))?;
+                Ok(ProverBackend::Network(
+                    ProverClient::builder().network().private_key(&key).build(),
+                    RetryPolicy::from_env(),
+                ))
+            }
+        }
+    }
+}
+
+/// Key generation for a program ELF, abstracted so setup failures can be simulated
+/// The key types are the backend's own, so a mock needs no SP1 setup
+pub trait ProgramSetup {
+    type ProvingKey: Send + Sync + 'static;
+    type VerifyingKey: Send + Sync + 'static;
+
+    /// Proving and verifying keys for `elf`; the SDK panics on a corrupt or mismatched ELF
+    fn setup(&self, elf: &[u8]) -> (Self::ProvingKey, Self::VerifyingKey);
+
+    /// On-chain digest of a verifying key (`0x`-prefixed bytes32 hex)
+    fn vkey_digest(verifying_key: &Self::VerifyingKey) -> String;
+}
+
+impl ProgramSetup for ProverBackend {
+    type ProvingKey = SP1ProvingKey;
+    type VerifyingKey = SP1VerifyingKey;
+
+    fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey) {
+        match self {
+            ProverBackend::Local(prover) => prover.setup(elf),
+            ProverBackend::Network(prover, _) => prover.setup(elf),
+        }
+    }
+
+    fn vkey_digest(verifying_key: &SP1VerifyingKey) -> String {
+        verifying_key.bytes32()
+    }
+}
+
+/// Execution, proving and local verification: the SP1 client the handlers drive,
+/// abstracted so handler logic can be tested without generating real proofs
+pub trait Prover: ProgramSetup + Send + Sync + 'static {
+    type Proof;
+
+    /// Backend name reported by `/health`
+    fn mode(&self) -> ProverMode;
+
+    /// Run the program without proving: its committed public values and total cycle count
+    fn execute(&self, elf: &[u8], stdin: &SP1Stdin) -> anyhow::Result<(Vec<u8>, u64)>;
+
+    fn prove(
+        &self,
+        proving_key: &Self::ProvingKey,
+        stdin: &SP1Stdin,
+        mode: SP1ProofMode,
+    ) -> anyhow::Result<Self::Proof>;
+
+    fn verify(
+        &self,
+        proof: &Self::Proof,
+        verifying_key: &Self::VerifyingKey,
+    ) -> Result<(), SP1VerificationError>;
+
+    /// Values committed by the proven program
+    fn public_values<'p>(&self, proof: &'p Self::Proof) -> &'p [u8];
+}
+
+impl Prover for ProverBackend {
+    type Proof = SP1ProofWithPublicValues;
+
+    fn mode(&self) -> ProverMode {
+        match self {
+            ProverBackend::Local(_) => ProverMode::Local,
+            ProverBackend::Network(..) => ProverMode::Network,
+        }
+    }
+
+    /// Execution is always local
+    fn execute(&self, elf: &[u8], stdin: &SP1Stdin) -> anyhow::Result<(Vec<u8>, u64)> {
+        let (public_values, report) = match self {
+            ProverBackend::Local(prover) => prover.execute(elf, stdin).run(),
+            ProverBackend::Network(prover, _) => prover.execute(elf, stdin).run(),
+        }?;
+        Ok((public_values.to_vec(), report.total_instruction_count()))
+    }
+
+    fn prove(
+        &self,
+        proving_key: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        mode: SP1ProofMode,
+    ) -> anyhow::Result<SP1ProofWithPublicValues> {
+        match self {
+            ProverBackend::Local(prover) => prover.prove(proving_key, stdin).mode(mode).run(),
+            ProverBackend::Network(prover, retry) => {
+                retry.run(|| prover.prove(proving_key, stdin).mode(mode).run())
+            }
+        }
+    }
+
+    fn verify(
+        &self,
+        proof: &SP1ProofWithPublicValues,
+        verifying_key: &SP1VerifyingKey,
+    ) -> Result<(), SP1VerificationError> {
+        match self {
+            ProverBackend::Local(prover) => prover.verify(proof, verifying_key),
+            ProverBackend::Network(prover, _) => prover.verify(proof, verifying_key),
+        }
+    }
+
+    fn public_values<'p>(&self, proof: &'p SP1ProofWithPublicValues) -> &'p [u8] {
+        proof.public_values.as_slice()
+    }
+}
+
+/// Prover answering instantly with canned public values, recording each call
+/// Its keys are placeholders derived from the ELF; its "proof" is the canned bytes
+#[cfg(test)]
+pub struct MockProver {
+    pub public_values: Vec<u8>,
+    /// Error every `execute` and `prove` fails with, when set
+    pub failure: Option<String>,
+    pub calls: std::sync::Mutex<Vec<&'static str>>,
+    /// Mode of each `prove` call, in order
+    pub modes: std::sync::Mutex<Vec<SP1ProofMode>>,
+}
+
+#[cfg(test)]
+impl MockProver {
+    pub fn new(public_values: Vec<u8>) -> Self {
+        Self {
+            public_values,
+            failure: None,
+            calls: Default::default(),
+            modes: Default::default(),
+        }
+    }
+
+    pub fn failing(message: &str) -> Self {
+        Self {
+            failure: Some(message.to_string()),
+            ..Self::new(Vec::new())
+        }
+    }
+
+    /// Calls so far, in order
+    pub fn calls(&self) -> Vec<&'static str> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Proof modes requested so far, in order
+    pub fn modes(&self) -> Vec<SP1ProofMode> {
+        self.modes.lock().unwrap().clone()
+    }
+
+    fn record(&self, call: &'static str) -> anyhow::Result<Vec<u8>> {
+        self.calls.lock().unwrap().push(call);
+        match &self.failure {
+            Some(message) => Err(anyhow::anyhow!("{}", message)),
+            None => Ok(self.public_values.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+impl ProgramSetup for MockProver {
+    type ProvingKey = ();
+    type VerifyingKey = String;
+
+    /// The "verifying key" is a hash of the ELF, so different programs get different keys
+    fn setup(&self, elf: &[u8]) -> ((), String) {
+        use std::hash::{DefaultHasher, Hash, Hasher};
+        let mut hasher = DefaultHasher::new();
+        elf.hash(&mut hasher);
+        ((), format!("0x{:064x}", hasher.finish()))
+    }
+
+    fn vkey_digest(verifying_key: &String) -> String {
+        verifying_key.clone()
+    }
+}
+
+#[cfg(test)]
+impl Prover for MockProver {
+    type Proof = Vec<u8>;
+
+    fn mode(&self) -> ProverMode {
+        ProverMode::Local
+    }
+
+    fn execute(&self, _elf: &[u8], _stdin: &SP1Stdin) -> anyhow::Result<(Vec<u8>, u64)> {
+        Ok((self.record("execute")?, 1))
+    }
+
+    fn prove(
+        &self,
+        _proving_key: &(),
+        _stdin: &SP1Stdin,
+        mode: SP1ProofMode,
+    ) -> anyhow::Result<Vec<u8>> {
+        self.modes.lock().unwrap().push(mode);
+        self.record("prove")
+    }
+
+    fn verify(
+        &self,
+        _proof: &Vec<u8>,
+        _verifying_key: &String,
+    ) -> Result<(), SP1VerificationError> {
+        self.calls.lock().unwrap().push("verify");
+        Ok(())
+    }
+
+    fn public_values<'p>(&self, proof: &'p Vec<u8>) -> &'p [u8] {
+        proof
+    }
+}
+
+/// Whether each proof is verified locally before it is returned, from `VERIFY_AFTER_PROVE`
+/// (default true); trusted setups that verify on-chain anyway can turn it off
+pub fn verify_after_prove() -> bool {
+    parse_verify_after_prove(std::env::var("VERIFY_AFTER_PROVE").ok().as_deref())
+}
+
+fn parse_verify_after_prove(value: Option<&str>) -> bool {
+    !matches!(
+        value.map(|v| v.trim().to_ascii_lowercase()).as_deref(),
+        Some("false" | "0" | "no" | "off")
+    )
+}
+
+/// How often and how patiently to retry transient network proving failures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts including the first; 1 disables retries
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubles after each failed attempt
+    pub initial_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Policy from `PROVER_MAX_ATTEMPTS` (default 3) and `PROVER_RETRY_BACKOFF_MS`
+    /// (default 2000)
+    pub fn from_env() -> Self {
+        let env_u64 = |name| -> Option<u64> { std::env::var(name).ok()?.trim().parse().ok() };
+        Self {
+            max_attempts: env_u64("PROVER_MAX_ATTEMPTS")
+                .map_or(DEFAULT_MAX_ATTEMPTS, |n| n.clamp(1, u32::MAX as u64) as u32),
+            initial_backoff: env_u64("PROVER_RETRY_BACKOFF_MS")
+                .map_or(DEFAULT_INITIAL_BACKOFF, Duration::from_millis),
+        }
+    }
+
+    /// Run `attempt` until it succeeds, fails with a non-retryable error, or runs out of
+    /// attempts (blocking: sleeps between attempts)
+    pub fn run<T>(self, mut attempt: impl FnMut() -> anyhow::Result<T>) -> anyhow::Result<T> {
+        let mut backoff = self.initial_backoff;
+        let mut attempts = 1;
+        loop {
+            match attempt() {
+                Ok(value) => return Ok(value),
+                Err(e) if attempts < self.max_attempts && is_retryable(&e) => {
+                    warn!(
+                        "Proving attempt {}/{} failed, retrying in {:?}: {}",
+                        attempts, self.max_attempts, backoff, e
+                    );
+                    std::thread::sleep(backoff);
+                    backoff = backoff.saturating_mul(2);
+                    attempts += 1;
+                }
+                Err(e) => {
+                    warn!(
+                        "Proving attempt {}/{} failed: {}",
+                        attempts, self.max_attempts, e
+                    );
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+        }
+    }
+}
+
+/// Whether a proving error looks transient (network, timeout, rate limit) rather than
+/// a fault of the input or program, which would fail again on retry
+fn is_retryable(error: &anyhow::Error) -> bool {
+    const TRANSIENT: [&str; 8] = [
+        "timeout",
+        "timed out",
+        "connection",
+        "network",
+        "unavailable",
+        "rate limit",
+        "too many requests",
+        "transport",
+    ];
+    let message = format!("{:#}", error).to_ascii_lowercase();
+    TRANSIENT.iter().any(|marker| message.contains(marker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prover_mode_selects_backend() {
+        assert_eq!(ProverMode::parse(None).unwrap(), ProverMode::Local);
+        assert_eq!(ProverMode::parse(Some("local")).unwrap(), ProverMode::Local);
+        assert_eq!(
+            ProverMode::parse(Some(" Network ")).unwrap(),
+            ProverMode::Network
+        );
+        assert!(ProverMode::parse(Some("cuda")).is_err());
+        assert_eq!(ProverMode::Network.as_str(), "network");
+
+        // Each mode builds its own backend; building never contacts the network
+        assert!(matches!(
+            ProverBackend::build(ProverMode::Local, None),
+            Ok(ProverBackend::Local(_))
+        ));
+        let key = format!("0x{}", "11".repeat(32));
+        assert!(matches!(
+            ProverBackend::build(ProverMode::Network, Some(key)),
+            Ok(ProverBackend::Network(..))
+        ));
+        assert!(ProverBackend::build(ProverMode::Network, None).is_err());
+    }
+
+    #[test]
+    fn test_verify_after_prove_defaults_on() {
+        assert!(parse_verify_after_prove(None));
+        assert!(parse_verify_after_prove(Some("true")));
+        assert!(parse_verify_after_prove(Some("")));
+        assert!(!parse_verify_after_prove(Some("false")));
+        assert!(!parse_verify_after_prove(Some(" 0 ")));
+    }
+
+    #[test]
+    fn test_retry_policy_retries_transient_failures() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::ZERO,
+        };
+
+        // Mock prover: two network failures, then a proof
+        let mut attempts = 0;
+        let result = policy.run(|| {
+            attempts += 1;
+            match attempts {
+                1 | 2 => Err(anyhow::anyhow!("network request timed out")),
+                _ => Ok("proof"),
+            }
+        });
+        assert_eq!(result.unwrap(), "proof");
+        assert_eq!(attempts, 3);
+
+        // Input faults are not retried
+        let mut attempts = 0;
+        let result: anyhow::Result<()> = policy.run(|| {
+            attempts += 1;
+            Err(anyhow::anyhow!(
+                "guest panicked: Transaction verification failed"
+            ))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+
+        // Transient failures stop after max_attempts
+        let mut attempts = 0;
+        let result: anyhow::Result<()> = policy.run(|| {
+            attempts += 1;
+            Err(anyhow::anyhow!("service unavailable"))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 3);
+    }
+}
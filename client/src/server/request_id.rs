@@ -0,0 +1,86 @@
+use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
+use tracing::{info_span, Instrument};
+use uuid::Uuid;
+
+/// Response header echoing the request's correlation id
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Correlation id of the request being handled, if called under `request_id_middleware`
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Tag each request with a UUID: handlers run inside a `request` span carrying it,
+/// and the id is echoed in the `x-request-id` response header
+pub async fn request_id_middleware(request: Request, next: Next) -> Response {
+    let request_id = Uuid::new_v4().to_string();
+    let span = info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+
+    let mut response = REQUEST_ID
+        .scope(request_id.clone(), next.run(request).instrument(span))
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::{handlers::generate_bitcoin_proof, prover::MockProver, state::AppState};
+    use axum::{middleware, routing::post, Router};
+    use reqwest::StatusCode;
+    use tracing_test::traced_test;
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_request_id_in_response_and_logs() {
+        let app = Router::new()
+            .route("/prove", post(generate_bitcoin_proof))
+            .layer(middleware::from_fn(request_id_middleware))
+            .with_state(AppState::new(MockProver::new(Vec::new())));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        // A txid mismatch is rejected inside the handler, which logs a warning
+        let body = serde_json::json!({
+            "tx": "00",
+            "tx_hash": "00",
+            "merkle": [],
+            "position": 0,
+            "block_header": "00",
+        });
+        let response = reqwest::Client::new()
+            .post(format!("http://{}/prove", addr))
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let header = response.headers()[REQUEST_ID_HEADER]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let json: serde_json::Value = response.json().await.unwrap();
+        let request_id = json["request_id"].as_str().unwrap();
+
+        assert!(Uuid::parse_str(request_id).is_ok());
+        assert_eq!(request_id, header);
+        assert!(logs_contain(request_id));
+
+        // Outside the middleware there is no request id
+        assert_eq!(current_request_id(), None);
+    }
+}
@@ -0,0 +1,348 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, OnceLock,
+};
+
+use tokio::sync::OwnedSemaphorePermit;
+use tracing::info;
+
+use crate::server::handlers::{BATCH_PROOF_ELF, BITCOIN_PROOF_ELF};
+use crate::server::jobs::JobRegistry;
+use crate::server::limits::ProofLimiter;
+use crate::server::prover::{ProgramSetup, Prover, ProverBackend, ProverMode};
+use crate::TARGET_ADDRESS;
+
+/// Proving and verification key for one program, in the types of the prover `S`
+pub struct ProgramKeys<S: ProgramSetup = ProverBackend> {
+    pub proving_key: S::ProvingKey,
+    pub verifying_key: S::VerifyingKey,
+}
+
+impl<S: ProgramSetup> ProgramKeys<S> {
+    /// Keys for `elf`, turning a setup panic (corrupt or mismatched ELF) into an error
+    fn setup<T>(prover: &T, elf: &[u8]) -> Result<Self, String>
+    where
+        T: ProgramSetup<ProvingKey = S::ProvingKey, VerifyingKey = S::VerifyingKey>,
+    {
+        let (proving_key, verifying_key) = catch_unwind(AssertUnwindSafe(|| prover.setup(elf)))
+            .map_err(|panic| {
+                panic
+                    .downcast_ref::<&str>()
+                    .map(|msg| msg.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "setup panicked".to_string())
+            })?;
+        Ok(Self {
+            proving_key,
+            verifying_key,
+        })
+    }
+
+    /// On-chain digest of the verifying key
+    pub fn vkey_digest(&self) -> String {
+        S::vkey_digest(&self.verifying_key)
+    }
+}
+
+/// The single-transaction program's ELF: embedded at build time, or loaded from disk
+/// so operators can swap in a newer program without recompiling the server
+#[derive(Clone)]
+pub struct ProgramElf {
+    bytes: Arc<[u8]>,
+    source: String,
+}
+
+impl ProgramElf {
+    /// The ELF compiled into the server
+    pub fn embedded() -> Self {
+        Self {
+            bytes: BITCOIN_PROOF_ELF.into(),
+            source: "embedded".to_string(),
+        }
+    }
+
+    /// Read an ELF file, rejecting anything without the ELF magic number
+    pub fn load(path: &str) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("cannot read ELF {}: {}", path, e))?;
+        if !bytes.starts_with(b"\x7fELF") {
+            return Err(format!("{} is not an ELF file", path));
+        }
+        Ok(Self {
+            bytes: bytes.into(),
+            source: path.to_string(),
+        })
+    }
+
+    /// ELF from `PROGRAM_ELF_PATH`, or the embedded one when unset
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var("PROGRAM_ELF_PATH") {
+            Ok(path) if !path.trim().is_empty() => {
+                Self::load(path.trim()).map_err(|e| format!("PROGRAM_ELF_PATH: {}", e))
+            }
+            _ => Ok(Self::embedded()),
+        }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// `embedded` or the path the ELF was loaded from
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+/// Keys for every program the server proves
+pub struct ProverSetup<S: ProgramSetup = ProverBackend> {
+    pub single: ProgramKeys<S>,
+    pub batch: ProgramKeys<S>,
+}
+
+/// Running totals of proof attempts, reported by `/health`
+#[derive(Default)]
+pub struct ProofStats {
+    total: AtomicU64,
+    succeeded: AtomicU64,
+    /// Duration of the latest attempt plus one; 0 until the first attempt
+    last_proof_ms: AtomicU64,
+}
+
+impl ProofStats {
+    /// Record a finished proof attempt and how long it took
+    pub fn record(&self, success: bool, elapsed_ms: u64) {
+        self.total.fetch_add(1, Ordering::Relaxed);
+        if success {
+            self.succeeded.fetch_add(1, Ordering::Relaxed);
+        }
+        self.last_proof_ms
+            .store(elapsed_ms.saturating_add(1), Ordering::Relaxed);
+    }
+
+    /// Proof attempts so far, successful or not
+    pub fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// Fraction of attempts that succeeded; 0 before the first attempt
+    pub fn success_rate(&self) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => self.succeeded.load(Ordering::Relaxed) as f64 / total as f64,
+        }
+    }
+
+    /// Duration of the latest attempt in milliseconds, None before the first
+    pub fn last_proof_ms(&self) -> Option<u64> {
+        self.last_proof_ms.load(Ordering::Relaxed).checked_sub(1)
+    }
+}
+
+/// State shared by all handlers; the prover setup is filled once, after startup
+/// Handlers are generic over the prover client `P` so tests can swap in a mock
+pub struct AppState<P: Prover = ProverBackend> {
+    setup: Arc<OnceLock<Arc<ProverSetup<P>>>>,
+    limiter: ProofLimiter,
+    client: Arc<P>,
+    elf: ProgramElf,
+    target_address: Arc<str>,
+    stats: Arc<ProofStats>,
+    jobs: JobRegistry,
+}
+
+impl<P: Prover> Clone for AppState<P> {
+    fn clone(&self) -> Self {
+        Self {
+            setup: self.setup.clone(),
+            limiter: self.limiter.clone(),
+            client: self.client.clone(),
+            elf: self.elf.clone(),
+            target_address: self.target_address.clone(),
+            stats: self.stats.clone(),
+            jobs: self.jobs.clone(),
+        }
+    }
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new(ProverBackend::local())
+    }
+}
+
+impl<P: Prover> AppState<P> {
+    /// State proving with `client`
+    pub fn new(client: P) -> Self {
+        Self {
+            setup: Arc::default(),
+            limiter: ProofLimiter::default(),
+            client: Arc::new(client),
+            elf: ProgramElf::embedded(),
+            target_address: TARGET_ADDRESS.into(),
+            stats: Arc::default(),
+            jobs: JobRegistry::default(),
+        }
+    }
+
+    /// Replace the proof concurrency limits
+    pub fn with_limiter(mut self, limiter: ProofLimiter) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// Prove and execute a different single-transaction program ELF
+    pub fn with_program_elf(mut self, elf: ProgramElf) -> Self {
+        self.elf = elf;
+        self
+    }
+
+    /// Single-transaction program ELF
+    pub fn program_elf(&self) -> &ProgramElf {
+        &self.elf
+    }
+
+    /// Sum outputs to `address` instead of the server's default target
+    pub fn with_target_address(mut self, address: &str) -> Self {
+        self.target_address = address.into();
+        self
+    }
+
+    /// Address proofs sum outputs to
+    pub fn target_address(&self) -> &str {
+        &self.target_address
+    }
+
+    /// Client proofs are generated with
+    pub fn client(&self) -> Arc<P> {
+        self.client.clone()
+    }
+
+    /// Backend proofs are generated with
+    pub fn prover_mode(&self) -> ProverMode {
+        self.client.mode()
+    }
+
+    /// Proof attempt totals, shared with tasks that outlive the request
+    pub fn proof_stats(&self) -> &Arc<ProofStats> {
+        &self.stats
+    }
+
+    /// Background proof jobs
+    pub fn jobs(&self) -> &JobRegistry {
+        &self.jobs
+    }
+
+    /// Wait for a proof generation slot, or None when the proof queue is full
+    pub async fn acquire_proof_slot(&self) -> Option<OwnedSemaphorePermit> {
+        self.limiter.acquire().await
+    }
+
+    /// Cached prover setup, or None while setup is still running
+    pub fn prover(&self) -> Option<Arc<ProverSetup<P>>> {
+        self.setup.get().cloned()
+    }
+
+    /// Run `client.setup` for every program and cache the keys (blocking)
+    /// An error means the server cannot prove at all; the caller should stop
+    pub fn run_setup(&self) -> Result<(), String> {
+        if self.setup.get().is_some() {
+            return Ok(());
+        }
+        self.setup_with(&*self.client)
+    }
+
+    /// Derive and cache every program's keys with `prover`, logging their vkey digests
+    fn setup_with<T>(&self, prover: &T) -> Result<(), String>
+    where
+        T: ProgramSetup<ProvingKey = P::ProvingKey, VerifyingKey = P::VerifyingKey>,
+    {
+        let start_time = std::time::Instant::now();
+        let setup = ProverSetup {
+            single: ProgramKeys::setup(prover, self.elf.bytes())
+                .map_err(|e| format!("single-transaction program setup failed: {}", e))?,
+            batch: ProgramKeys::setup(prover, BATCH_PROOF_ELF)
+                .map_err(|e| format!("batch program setup failed: {}", e))?,
+        };
+        info!(
+            "Program vkeys: single {} ({} ELF), batch {}",
+            setup.single.vkey_digest(),
+            self.elf.source(),
+            setup.batch.vkey_digest()
+        );
+        // A concurrent setup may have won the race; its keys are identical
+        let _ = self.setup.set(Arc::new(setup));
+        info!(
+            "{} prover setup finished in {:?}",
+            self.prover_mode().as_str(),
+            start_time.elapsed()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::prover::MockProver;
+
+    /// Prover whose setup panics, as the SDK does on a corrupt ELF
+    struct CorruptElfProver;
+
+    impl ProgramSetup for CorruptElfProver {
+        type ProvingKey = ();
+        type VerifyingKey = String;
+
+        fn setup(&self, _elf: &[u8]) -> ((), String) {
+            panic!("invalid ELF header")
+        }
+
+        fn vkey_digest(verifying_key: &String) -> String {
+            verifying_key.clone()
+        }
+    }
+
+    #[test]
+    fn test_setup_failure_is_an_error() {
+        let state = AppState::new(MockProver::new(Vec::new()));
+        let error = state.setup_with(&CorruptElfProver).unwrap_err();
+        assert!(
+            error.contains("single-transaction program") && error.contains("invalid ELF header"),
+            "unexpected error: {}",
+            error
+        );
+        // Nothing is cached, so the server never reports ready with broken keys
+        assert!(state.prover().is_none());
+    }
+
+    #[test]
+    fn test_program_elf_loaded_from_path() {
+        let dir = std::env::temp_dir().join(format!("program-elf-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let elf_path = dir.join("program.elf");
+        let mut elf = b"\x7fELF".to_vec();
+        elf.extend_from_slice(&[2, 1, 1, 0]);
+        std::fs::write(&elf_path, &elf).unwrap();
+        let loaded = ProgramElf::load(elf_path.to_str().unwrap()).unwrap();
+        assert_eq!(loaded.bytes(), elf.as_slice());
+        assert_eq!(loaded.source(), elf_path.to_str().unwrap());
+        let state = AppState::new(MockProver::new(Vec::new())).with_program_elf(loaded);
+        assert_eq!(state.program_elf().bytes(), elf.as_slice());
+
+        // A missing file and a non-ELF file are startup errors naming the path
+        let missing = dir.join("missing.elf");
+        let error = ProgramElf::load(missing.to_str().unwrap()).err().unwrap();
+        assert!(
+            error.contains("cannot read ELF") && error.contains("missing.elf"),
+            "{}",
+            error
+        );
+        let text_path = dir.join("program.txt");
+        std::fs::write(&text_path, "not a program").unwrap();
+        let error = ProgramElf::load(text_path.to_str().unwrap()).err().unwrap();
+        assert!(error.contains("is not an ELF file"), "{}", error);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -6,6 +6,28 @@ import "../lib/sp1-contracts/contracts/lib/openzeppelin-contracts/contracts/util
 import "../lib/sp1-contracts/contracts/src/v1.1.0/SP1Verifier.sol";
 import "./zkBTC.sol";
 
+/// @notice Public values of the Bitcoin transaction program, ABI-encoded
+/// @dev Regenerate with `cargo run --release --bin gen-abi` in `script`
+struct PublicValuesStruct {
+    string blockHash;
+    uint64 totalAmount;
+    bytes32 txid;
+    uint32 outputIndex;
+    bool isCoinbase;
+    string targetAddress;
+    bool opReturnBytesCommitted;
+    uint32 opReturnBytes;
+    bool inputsChecked;
+    bool allInputsConfirmed;
+    string[] fundingBlockHashes;
+    bool blockHashPinned;
+    bool coinbaseRejected;
+    bool noMatchAllowed;
+    bool opReturnChecked;
+    bytes32 expectedOpReturn;
+    bool opReturnAnchored;
+}
+
 /**
  * @title zkBTCVault
  * @dev Vault contract that deploys zkBTC with zero initial supply and only allows minting
@@ -60,56 +82,32 @@ contract zkBTCVault is Ownable, ReentrancyGuard {
         zkbtcToken = new zkBTC(address(this));
     }
 
+    /**
+     * @dev Internal function to decode the public values committed by the program
+     * @param publicValues The encoded public values from the zk proof
+     * @return values The decoded public values
+     * @notice Format: abi.encode(PublicValuesStruct)
+     */
+    function _decodePublicValues(bytes calldata publicValues) internal pure returns (PublicValuesStruct memory values) {
+        return abi.decode(publicValues, (PublicValuesStruct));
+    }
+
     /**
      * @dev Internal function to extract block hash from public values
      * @param publicValues The encoded public values from the zk proof
      * @return blockHash The extracted block hash from the public values
-     * @notice Format: [8-byte length][block_hash string][8-byte total_amount]
      */
     function _extractBlockHashFromPublicValues(bytes calldata publicValues) internal pure returns (string memory blockHash) {
-        // Check minimum length: 8 bytes for length + at least 1 byte for block_hash + 8 bytes for amount
-        if (publicValues.length < 17) {
-            return "";
-        }
-        
-        // Read the length of the block_hash string (first 8 bytes as little-endian uint64)
-        uint64 blockHashLen = uint64(bytes8(publicValues[0:8]));
-        
-        // Check if we have enough data: 8 bytes length + block_hash + 8 bytes amount
-        if (publicValues.length < 8 + blockHashLen + 8) {
-            return "";
-        }
-        
-        // Extract the block_hash string
-        bytes memory blockHashBytes = publicValues[8:8 + blockHashLen];
-        return string(blockHashBytes);
+        return _decodePublicValues(publicValues).blockHash;
     }
 
     /**
      * @dev Internal function to extract amount from public values
      * @param publicValues The encoded public values from the zk proof
      * @return amount The extracted amount from the public values
-     * @notice Format: [8-byte length][block_hash string][8-byte total_amount]
      */
     function _extractAmountFromPublicValues(bytes calldata publicValues) internal pure returns (uint256 amount) {
-        // Check minimum length: 8 bytes for length + at least 1 byte for block_hash + 8 bytes for amount
-        if (publicValues.length < 17) {
-            return 0;
-        }
-        
-        // Read the length of the block_hash string (first 8 bytes as little-endian uint64)
-        uint64 blockHashLen = uint64(bytes8(publicValues[0:8]));
-        
-        // Check if we have enough data: 8 bytes length + block_hash + 8 bytes amount
-        if (publicValues.length < 8 + blockHashLen + 8) {
-            return 0;
-        }
-        
-        // Extract the total_amount (last 8 bytes as little-endian uint64)
-        uint64 amountStart = 8 + blockHashLen;
-        amount = uint256(uint64(bytes8(publicValues[amountStart:amountStart + 8])));
-        
-        return amount;
+        return uint256(_decodePublicValues(publicValues).totalAmount);
     }
 
     /**
@@ -3,6 +3,7 @@ pragma solidity ^0.8.20;
 
 import "forge-std/Test.sol";
 import "../src/zkBTCVault.sol";
+import "./PublicValues.sol";
 import "../src/zkBTC.sol";
 import "../lib/sp1-contracts/contracts/src/v1.1.0/SP1Verifier.sol";
 
@@ -22,11 +23,7 @@ contract IntegrationTest is Test {
     bytes32 public constant MAINNET_TX_HASH_2 = 0x25e10745f15593a899cef391191bdd3d7c12412cc4696b7bcb669d0feadc8522;
     
     // Mock public values representing the real transaction
-    bytes public constant MAINNET_PUBLIC_VALUES = abi.encodePacked(
-        uint64(64), // Length of block hash string
-        "ef0c2fa8517414b742094a020da7eba891b47d660ef66f126ad01e5be99a2fd0", // Block hash
-        uint64(1240000000) // Amount: 12.4 BTC in satoshis
-    );
+    bytes public mainnetPublicValues = PublicValues.encode(1240000000);
     
     bytes public constant MAINNET_PROOF_BYTES = hex"1234567890abcdef1234567890abcdef";
     bytes public constant MAINNET_PROOF_BYTES_2 = hex"abcdef1234567890abcdef1234567890";
@@ -58,7 +55,7 @@ contract IntegrationTest is Test {
         
         // Step 1: User calls mintWithProof
         vm.prank(user);
-        vault.mintWithProof(user, MAINNET_TX_HASH, MAINNET_PUBLIC_VALUES, MAINNET_PROOF_BYTES);
+        vault.mintWithProof(user, MAINNET_TX_HASH, mainnetPublicValues, MAINNET_PROOF_BYTES);
         
         // Step 2: Verify token was minted correctly
         assertEq(zkbtc.balanceOf(user), expectedAmount);
@@ -74,7 +71,7 @@ contract IntegrationTest is Test {
         
         // Step 4: Another user mints with different transaction
         vm.prank(anotherUser);
-        vault.mintWithProof(anotherUser, MAINNET_TX_HASH_2, MAINNET_PUBLIC_VALUES, MAINNET_PROOF_BYTES_2);
+        vault.mintWithProof(anotherUser, MAINNET_TX_HASH_2, mainnetPublicValues, MAINNET_PROOF_BYTES_2);
         
         assertEq(zkbtc.balanceOf(anotherUser), transferAmount + expectedAmount);
         assertEq(zkbtc.totalSupply(), expectedAmount * 2);
@@ -85,11 +82,11 @@ contract IntegrationTest is Test {
         
         // User 1 mints
         vm.prank(user);
-        vault.mintWithProof(user, MAINNET_TX_HASH, MAINNET_PUBLIC_VALUES, MAINNET_PROOF_BYTES);
+        vault.mintWithProof(user, MAINNET_TX_HASH, mainnetPublicValues, MAINNET_PROOF_BYTES);
         
         // User 2 mints with different transaction
         vm.prank(anotherUser);
-        vault.mintWithProof(anotherUser, MAINNET_TX_HASH_2, MAINNET_PUBLIC_VALUES, MAINNET_PROOF_BYTES_2);
+        vault.mintWithProof(anotherUser, MAINNET_TX_HASH_2, mainnetPublicValues, MAINNET_PROOF_BYTES_2);
         
         // Verify both users have tokens
         assertEq(zkbtc.balanceOf(user), expectedAmount);
@@ -106,7 +103,7 @@ contract IntegrationTest is Test {
         
         // Mint through vault
         vm.prank(user);
-        vault.mintWithProof(user, MAINNET_TX_HASH, MAINNET_PUBLIC_VALUES, MAINNET_PROOF_BYTES);
+        vault.mintWithProof(user, MAINNET_TX_HASH, mainnetPublicValues, MAINNET_PROOF_BYTES);
         
         // Verify vault and token are consistent
         assertEq(vault.getMintedAmount(MAINNET_TX_HASH), expectedAmount);
@@ -126,12 +123,12 @@ contract IntegrationTest is Test {
     function testReplayProtectionAcrossUsers() public {
         // User 1 mints
         vm.prank(user);
-        vault.mintWithProof(user, MAINNET_TX_HASH, MAINNET_PUBLIC_VALUES, MAINNET_PROOF_BYTES);
+        vault.mintWithProof(user, MAINNET_TX_HASH, mainnetPublicValues, MAINNET_PROOF_BYTES);
         
         // User 2 tries to use same proof (should fail)
         vm.prank(anotherUser);
         vm.expectRevert(zkBTCVault.ProofAlreadyUsed.selector);
-        vault.mintWithProof(user, MAINNET_TX_HASH, MAINNET_PUBLIC_VALUES, MAINNET_PROOF_BYTES);
+        vault.mintWithProof(user, MAINNET_TX_HASH, mainnetPublicValues, MAINNET_PROOF_BYTES);
         
         // Verify only user 1 has tokens
         assertEq(zkbtc.balanceOf(user), 1240000000);
@@ -144,7 +141,7 @@ contract IntegrationTest is Test {
         
         // Mint tokens
         vm.prank(user);
-        vault.mintWithProof(user, MAINNET_TX_HASH, MAINNET_PUBLIC_VALUES, MAINNET_PROOF_BYTES);
+        vault.mintWithProof(user, MAINNET_TX_HASH, mainnetPublicValues, MAINNET_PROOF_BYTES);
         
         // Transfer tokens
         vm.prank(user);
@@ -165,7 +162,7 @@ contract IntegrationTest is Test {
         
         // Mint tokens
         vm.prank(user);
-        vault.mintWithProof(user, MAINNET_TX_HASH, MAINNET_PUBLIC_VALUES, MAINNET_PROOF_BYTES);
+        vault.mintWithProof(user, MAINNET_TX_HASH, mainnetPublicValues, MAINNET_PROOF_BYTES);
         
         // Approve tokens
         vm.prank(user);
@@ -184,11 +181,7 @@ contract IntegrationTest is Test {
     function testMaxSupplyScenario() public {
         // Create public values with amount close to max supply
         uint256 largeAmount = 20_000_000 * 10**8; // 20M BTC
-        bytes memory largeAmountValues = abi.encodePacked(
-            uint64(64),
-            "ef0c2fa8517414b742094a020da7eba891b47d660ef66f126ad01e5be99a2fd0",
-            uint64(largeAmount)
-        );
+        bytes memory largeAmountValues = PublicValues.encode(largeAmount);
         
         // First mint should succeed
         vm.prank(user);
@@ -198,11 +191,7 @@ contract IntegrationTest is Test {
         
         // Second mint with remaining supply should succeed
         uint256 remainingAmount = 1_000_000 * 10**8; // 1M BTC
-        bytes memory remainingAmountValues = abi.encodePacked(
-            uint64(64),
-            "ef0c2fa8517414b742094a020da7eba891b47d660ef66f126ad01e5be99a2fd0",
-            uint64(remainingAmount)
-        );
+        bytes memory remainingAmountValues = PublicValues.encode(remainingAmount);
         
         vm.prank(anotherUser);
         vault.mintWithProof(anotherUser, MAINNET_TX_HASH_2, remainingAmountValues, MAINNET_PROOF_BYTES_2);
@@ -215,7 +204,7 @@ contract IntegrationTest is Test {
         uint256 gasStart = gasleft();
         
         vm.prank(user);
-        vault.mintWithProof(user, MAINNET_TX_HASH, MAINNET_PUBLIC_VALUES, MAINNET_PROOF_BYTES);
+        vault.mintWithProof(user, MAINNET_TX_HASH, mainnetPublicValues, MAINNET_PROOF_BYTES);
         
         uint256 vaultGas = gasStart - gasleft();
         
@@ -234,13 +223,13 @@ contract IntegrationTest is Test {
     }
 
     function testEventEmission() public {
-        bytes32 expectedProofHash = keccak256(abi.encodePacked(MAINNET_TX_HASH, MAINNET_PUBLIC_VALUES, MAINNET_PROOF_BYTES));
+        bytes32 expectedProofHash = keccak256(abi.encodePacked(MAINNET_TX_HASH, mainnetPublicValues, MAINNET_PROOF_BYTES));
         
         vm.prank(user);
         vm.expectEmit(true, true, true, true);
         emit TokensMinted(user, 1240000000, MAINNET_TX_HASH, expectedProofHash);
         
-        vault.mintWithProof(user, MAINNET_TX_HASH, MAINNET_PUBLIC_VALUES, MAINNET_PROOF_BYTES);
+        vault.mintWithProof(user, MAINNET_TX_HASH, mainnetPublicValues, MAINNET_PROOF_BYTES);
     }
 
     function testFuzzIntegration(uint256 amount1, uint256 amount2) public {
@@ -251,8 +240,8 @@ contract IntegrationTest is Test {
         vm.assume(amount1 + amount2 <= 21_000_000 * 10**8);
         
         // Create public values for amounts
-        bytes memory values1 = abi.encodePacked(uint64(64), "ef0c2fa8517414b742094a020da7eba891b47d660ef66f126ad01e5be99a2fd0", uint64(amount1));
-        bytes memory values2 = abi.encodePacked(uint64(64), "ef0c2fa8517414b742094a020da7eba891b47d660ef66f126ad01e5be99a2fd0", uint64(amount2));
+        bytes memory values1 = PublicValues.encode(amount1);
+        bytes memory values2 = PublicValues.encode(amount2);
         
         // Mint for both users
         vm.prank(user);
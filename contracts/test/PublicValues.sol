@@ -0,0 +1,44 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "../src/zkBTCVault.sol";
+
+/**
+ * @title PublicValues
+ * @dev Test helper encoding public values the way the Bitcoin transaction program commits them
+ */
+library PublicValues {
+    /// @notice Block hash of the mainnet test transaction
+    string internal constant BLOCK_HASH = "ef0c2fa8517414b742094a020da7eba891b47d660ef66f126ad01e5be99a2fd0";
+
+    /// @notice Address the mainnet test transaction pays
+    string internal constant TARGET_ADDRESS = "1BUBQuPV3gEV7P2XLNuAJQjf5t265Yyj9t";
+
+    function encode(uint256 totalAmount) internal pure returns (bytes memory) {
+        return encode(BLOCK_HASH, totalAmount);
+    }
+
+    function encode(string memory blockHash, uint256 totalAmount) internal pure returns (bytes memory) {
+        return abi.encode(
+            PublicValuesStruct({
+                blockHash: blockHash,
+                totalAmount: uint64(totalAmount),
+                txid: bytes32(0),
+                outputIndex: 0,
+                isCoinbase: false,
+                targetAddress: TARGET_ADDRESS,
+                opReturnBytesCommitted: false,
+                opReturnBytes: 0,
+                inputsChecked: false,
+                allInputsConfirmed: false,
+                fundingBlockHashes: new string[](0),
+                blockHashPinned: false,
+                coinbaseRejected: false,
+                noMatchAllowed: false,
+                opReturnChecked: false,
+                expectedOpReturn: bytes32(0),
+                opReturnAnchored: false
+            })
+        );
+    }
+}
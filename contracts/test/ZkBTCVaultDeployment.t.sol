@@ -3,6 +3,7 @@ pragma solidity ^0.8.20;
 
 import "forge-std/Test.sol";
 import "../src/zkBTCVault.sol";
+import "./PublicValues.sol";
 import "../lib/sp1-contracts/contracts/src/v1.1.0/SP1Verifier.sol";
 
 /**
@@ -40,11 +41,7 @@ contract ZkBTCVaultDeploymentTest is Test {
         string memory blockHash = "0000000000000000000000000000000000000000000000000000000000000000";
         uint256 amount = 100000000; // 1 BTC in satoshis
         
-        bytes memory publicValues = abi.encodePacked(
-            uint64(bytes(blockHash).length), // 8-byte length
-            bytes(blockHash),                // block hash
-            uint64(amount)                  // 8-byte amount
-        );
+        bytes memory publicValues = PublicValues.encode(blockHash, amount);
         
         bytes memory proofBytes = "mock-proof";
         
@@ -67,11 +64,7 @@ contract ZkBTCVaultDeploymentTest is Test {
         string memory blockHash = "0000000000000000000000000000000000000000000000000000000000000000";
         uint256 amount = 100000000; // 1 BTC in satoshis
         
-        bytes memory publicValues = abi.encodePacked(
-            uint64(bytes(blockHash).length), // 8-byte length
-            bytes(blockHash),                // block hash
-            uint64(amount)                  // 8-byte amount
-        );
+        bytes memory publicValues = PublicValues.encode(blockHash, amount);
         
         bytes memory proofBytes = "mock-proof";
         
@@ -3,6 +3,7 @@ pragma solidity ^0.8.20;
 
 import "forge-std/Test.sol";
 import "../src/zkBTCVault.sol";
+import "./PublicValues.sol";
 import "../src/zkBTC.sol";
 import "../lib/sp1-contracts/contracts/src/v1.1.0/SP1Verifier.sol";
 
@@ -19,11 +20,7 @@ contract zkBTCVaultTest is Test {
     bytes32 public constant BITCOIN_TX_HASH = keccak256("bitcoin-tx-hash");
     
     // Mock public values for testing (format: [8-byte length][block_hash string][8-byte total_amount])
-    bytes public constant MOCK_PUBLIC_VALUES = abi.encodePacked(
-        uint64(64), // Length of block hash string (64 characters)
-        "ef0c2fa8517414b742094a020da7eba891b47d660ef66f126ad01e5be99a2fd0", // Block hash
-        uint64(1240000000) // Amount in satoshis (12.4 BTC)
-    );
+    bytes public mockPublicValues = PublicValues.encode(1240000000);
     
     bytes public constant MOCK_PROOF_BYTES = hex"1234567890abcdef";
     
@@ -67,41 +64,37 @@ contract zkBTCVaultTest is Test {
         
         vm.prank(user);
         vm.expectEmit(true, true, true, true);
-        emit TokensMinted(user, expectedAmount, BITCOIN_TX_HASH, keccak256(abi.encodePacked(BITCOIN_TX_HASH, MOCK_PUBLIC_VALUES, MOCK_PROOF_BYTES)));
+        emit TokensMinted(user, expectedAmount, BITCOIN_TX_HASH, keccak256(abi.encodePacked(BITCOIN_TX_HASH, mockPublicValues, MOCK_PROOF_BYTES)));
         
         vm.expectEmit(true, false, false, false);
         emit ProofVerified(BITCOIN_TX_HASH, true);
         
-        vault.mintWithProof(user, BITCOIN_TX_HASH, MOCK_PUBLIC_VALUES, MOCK_PROOF_BYTES);
+        vault.mintWithProof(user, BITCOIN_TX_HASH, mockPublicValues, MOCK_PROOF_BYTES);
         
         // Check token balances
         assertEq(zkbtc.totalSupply(), initialSupply + expectedAmount);
         assertEq(zkbtc.balanceOf(user), expectedAmount);
         
         // Check vault state
-        assertTrue(vault.isProofUsed(keccak256(abi.encodePacked(BITCOIN_TX_HASH, MOCK_PUBLIC_VALUES, MOCK_PROOF_BYTES))));
+        assertTrue(vault.isProofUsed(keccak256(abi.encodePacked(BITCOIN_TX_HASH, mockPublicValues, MOCK_PROOF_BYTES))));
         assertEq(vault.getMintedAmount(BITCOIN_TX_HASH), expectedAmount);
     }
 
     function testMintWithProofInvalidRecipient() public {
         vm.prank(user);
         vm.expectRevert(zkBTCVault.InvalidRecipient.selector);
-        vault.mintWithProof(address(0), BITCOIN_TX_HASH, MOCK_PUBLIC_VALUES, MOCK_PROOF_BYTES);
+        vault.mintWithProof(address(0), BITCOIN_TX_HASH, mockPublicValues, MOCK_PROOF_BYTES);
     }
 
     function testMintWithProofInvalidBitcoinTxHash() public {
         vm.prank(user);
         vm.expectRevert(zkBTCVault.InvalidBitcoinTxHash.selector);
-        vault.mintWithProof(user, bytes32(0), MOCK_PUBLIC_VALUES, MOCK_PROOF_BYTES);
+        vault.mintWithProof(user, bytes32(0), mockPublicValues, MOCK_PROOF_BYTES);
     }
 
     function testMintWithProofZeroAmount() public {
         // Create public values with zero amount
-        bytes memory zeroAmountValues = abi.encodePacked(
-            uint64(64), // Length of block hash string
-            "ef0c2fa8517414b742094a020da7eba891b47d660ef66f126ad01e5be99a2fd0", // Block hash
-            uint64(0) // Zero amount
-        );
+        bytes memory zeroAmountValues = PublicValues.encode(0);
         
         vm.prank(user);
         vm.expectRevert(zkBTCVault.ZeroMintAmount.selector);
@@ -110,21 +103,17 @@ contract zkBTCVaultTest is Test {
 
     function testMintWithProofReplayAttack() public {
         vm.prank(user);
-        vault.mintWithProof(user, BITCOIN_TX_HASH, MOCK_PUBLIC_VALUES, MOCK_PROOF_BYTES);
+        vault.mintWithProof(user, BITCOIN_TX_HASH, mockPublicValues, MOCK_PROOF_BYTES);
         
         // Try to use the same proof again
         vm.prank(anotherUser);
         vm.expectRevert(zkBTCVault.ProofAlreadyUsed.selector);
-        vault.mintWithProof(anotherUser, BITCOIN_TX_HASH, MOCK_PUBLIC_VALUES, MOCK_PROOF_BYTES);
+        vault.mintWithProof(anotherUser, BITCOIN_TX_HASH, mockPublicValues, MOCK_PROOF_BYTES);
     }
 
     function testMintWithProofMaxSupplyExceeded() public {
         // Create public values with very large amount
-        bytes memory largeAmountValues = abi.encodePacked(
-            uint64(64), // Length of block hash string
-            "ef0c2fa8517414b742094a020da7eba891b47d660ef66f126ad01e5be99a2fd0", // Block hash
-            uint64(21_000_000 * 10**8 + 1) // Amount exceeding max supply
-        );
+        bytes memory largeAmountValues = PublicValues.encode(21_000_000 * 10**8 + 1);
         
         vm.prank(user);
         vm.expectRevert(zkBTCVault.MintAmountExceedsMaxSupply.selector);
@@ -136,14 +125,14 @@ contract zkBTCVaultTest is Test {
         
         // First mint
         vm.prank(user);
-        vault.mintWithProof(user, BITCOIN_TX_HASH, MOCK_PUBLIC_VALUES, MOCK_PROOF_BYTES);
+        vault.mintWithProof(user, BITCOIN_TX_HASH, mockPublicValues, MOCK_PROOF_BYTES);
         
         // Create different proof for same transaction (different proof bytes)
         bytes memory differentProof = hex"abcdef1234567890";
         
         // Second mint with different proof but same transaction
         vm.prank(anotherUser);
-        vault.mintWithProof(anotherUser, BITCOIN_TX_HASH, MOCK_PUBLIC_VALUES, differentProof);
+        vault.mintWithProof(anotherUser, BITCOIN_TX_HASH, mockPublicValues, differentProof);
         
         // Check balances
         assertEq(zkbtc.balanceOf(user), expectedAmount);
@@ -165,14 +154,14 @@ contract zkBTCVaultTest is Test {
     }
 
     function testIsProofUsed() public {
-        bytes32 proofHash = keccak256(abi.encodePacked(BITCOIN_TX_HASH, MOCK_PUBLIC_VALUES, MOCK_PROOF_BYTES));
+        bytes32 proofHash = keccak256(abi.encodePacked(BITCOIN_TX_HASH, mockPublicValues, MOCK_PROOF_BYTES));
         
         // Initially not used
         assertFalse(vault.isProofUsed(proofHash));
         
         // After minting, should be used
         vm.prank(user);
-        vault.mintWithProof(user, BITCOIN_TX_HASH, MOCK_PUBLIC_VALUES, MOCK_PROOF_BYTES);
+        vault.mintWithProof(user, BITCOIN_TX_HASH, mockPublicValues, MOCK_PROOF_BYTES);
         
         assertTrue(vault.isProofUsed(proofHash));
     }
@@ -185,18 +174,14 @@ contract zkBTCVaultTest is Test {
         
         // After minting
         vm.prank(user);
-        vault.mintWithProof(user, BITCOIN_TX_HASH, MOCK_PUBLIC_VALUES, MOCK_PROOF_BYTES);
+        vault.mintWithProof(user, BITCOIN_TX_HASH, mockPublicValues, MOCK_PROOF_BYTES);
         
         assertEq(vault.getMintedAmount(BITCOIN_TX_HASH), expectedAmount);
     }
 
     function testDifferentPublicValuesFormats() public {
         // Test with different block hash lengths
-        bytes memory shortBlockHashValues = abi.encodePacked(
-            uint64(8), // Length of block hash string (8 characters)
-            "12345678", // Short block hash
-            uint64(1000000) // Amount
-        );
+        bytes memory shortBlockHashValues = PublicValues.encode("12345678", 1000000);
         
         vm.prank(user);
         vault.mintWithProof(user, BITCOIN_TX_HASH, shortBlockHashValues, MOCK_PROOF_BYTES);
@@ -204,11 +189,7 @@ contract zkBTCVaultTest is Test {
         assertEq(zkbtc.balanceOf(user), 1000000);
         
         // Test with longer block hash
-        bytes memory longBlockHashValues = abi.encodePacked(
-            uint64(128), // Length of block hash string (128 characters)
-            "ef0c2fa8517414b742094a020da7eba891b47d660ef66f126ad01e5be99a2fd0ef0c2fa8517414b742094a020da7eba891b47d660ef66f126ad01e5be99a2fd0", // Long block hash
-            uint64(5000000) // Amount
-        );
+        bytes memory longBlockHashValues = PublicValues.encode("ef0c2fa8517414b742094a020da7eba891b47d660ef66f126ad01e5be99a2fd0ef0c2fa8517414b742094a020da7eba891b47d660ef66f126ad01e5be99a2fd0", 5000000);
         
         bytes32 differentTxHash = keccak256("different-tx-hash");
         vm.prank(anotherUser);
@@ -218,12 +199,8 @@ contract zkBTCVaultTest is Test {
     }
 
     function testEdgeCasePublicValues() public {
-        // Test with minimum valid length
-        bytes memory minValidValues = abi.encodePacked(
-            uint64(1), // Length of block hash string (1 character)
-            "a", // Single character block hash
-            uint64(1) // Minimum amount
-        );
+        // Test with a one-character block hash
+        bytes memory minValidValues = PublicValues.encode("a", 1);
         
         bytes32 edgeTxHash = keccak256("edge-tx-hash");
         vm.prank(user);
@@ -239,11 +216,7 @@ contract zkBTCVaultTest is Test {
         vm.assume(amount <= 21_000_000 * 10**8); // Within max supply
         
         // Create public values with fuzzed amount
-        bytes memory fuzzValues = abi.encodePacked(
-            uint64(64), // Length of block hash string
-            "ef0c2fa8517414b742094a020da7eba891b47d660ef66f126ad01e5be99a2fd0", // Block hash
-            uint64(amount) // Fuzzed amount
-        );
+        bytes memory fuzzValues = PublicValues.encode(amount);
         
         uint256 initialSupply = zkbtc.totalSupply();
         
@@ -258,7 +231,7 @@ contract zkBTCVaultTest is Test {
         uint256 gasStart = gasleft();
         
         vm.prank(user);
-        vault.mintWithProof(user, BITCOIN_TX_HASH, MOCK_PUBLIC_VALUES, MOCK_PROOF_BYTES);
+        vault.mintWithProof(user, BITCOIN_TX_HASH, mockPublicValues, MOCK_PROOF_BYTES);
         
         uint256 gasUsed = gasStart - gasleft();
         
@@ -0,0 +1,141 @@
+//! Plain HTTP/1.0 exchange shared by the host-only fetchers
+//!
+//! Just enough HTTP for a bitcoind RPC port or a self-hosted explorer on the LAN:
+//! one request per connection, no TLS.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Parsed `http://host[:port][/path]` url
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HttpUrl {
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl HttpUrl {
+    /// Parse an `http://` url, using `default_port` when it names none
+    pub fn parse(url: &str, default_port: u16) -> Result<Self, String> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| format!("url must start with http://: {}", url))?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse::<u16>()
+                    .map_err(|e| format!("invalid port in {}: {}", url, e))?,
+            ),
+            None => (authority, default_port),
+        };
+        if host.is_empty() {
+            return Err(format!("missing host in url: {}", url));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+/// Send one request and return the status code and body
+/// `headers` are full header lines without the line break; HTTP/1.0 keeps the server
+/// from answering with a chunked body
+pub(crate) fn send(
+    url: &HttpUrl,
+    method: &str,
+    headers: &[String],
+    body: &str,
+    timeout: Duration,
+) -> Result<(u16, String), String> {
+    let mut stream = TcpStream::connect((url.host.as_str(), url.port))
+        .map_err(|e| format!("connect {}:{}: {}", url.host, url.port, e))?;
+    stream
+        .set_read_timeout(Some(timeout))
+        .and_then(|_| stream.set_write_timeout(Some(timeout)))
+        .map_err(|e| format!("http socket: {}", e))?;
+
+    let mut request = format!(
+        "{} {} HTTP/1.0\r\nHost: {}:{}\r\n",
+        method, url.path, url.host, url.port
+    );
+    for header in headers {
+        request.push_str(header);
+        request.push_str("\r\n");
+    }
+    request.push_str("\r\n");
+    request.push_str(body);
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| format!("http write: {}", e))?;
+
+    let mut raw = Vec::new();
+    stream
+        .read_to_end(&mut raw)
+        .map_err(|e| format!("http read: {}", e))?;
+    let raw = String::from_utf8(raw).map_err(|e| format!("http response not utf-8: {}", e))?;
+    let (head, body) = raw
+        .split_once("\r\n\r\n")
+        .ok_or("malformed http response")?;
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or("missing http status")?;
+    Ok((status, body.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_http_url_and_exchange() {
+        assert!(HttpUrl::parse("https://node:8332", 8332).is_err());
+        assert!(HttpUrl::parse("http://:8332", 8332).is_err());
+        assert!(HttpUrl::parse("http://node:port", 8332).is_err());
+        assert_eq!(
+            HttpUrl::parse("http://127.0.0.1:18443/wallet/w1", 8332).unwrap(),
+            HttpUrl {
+                host: "127.0.0.1".to_string(),
+                port: 18443,
+                path: "/wallet/w1".to_string(),
+            }
+        );
+        let default_port = HttpUrl::parse("http://node", 80).unwrap();
+        assert_eq!((default_port.port, default_port.path.as_str()), (80, "/"));
+
+        // One request against a local server that echoes the request line
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.ends_with(b"ping") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request = String::from_utf8(request).unwrap();
+            let request_line = request.lines().next().unwrap().to_string();
+            write!(stream, "HTTP/1.0 201 Created\r\n\r\n{}", request_line).unwrap();
+            request
+        });
+
+        let url = HttpUrl::parse(&format!("http://127.0.0.1:{}/api", port), 80).unwrap();
+        let headers = ["Content-Length: 4".to_string()];
+        let (status, body) = send(&url, "POST", &headers, "ping", Duration::from_secs(5)).unwrap();
+        assert_eq!(status, 201);
+        assert_eq!(body, "POST /api HTTP/1.0");
+        let request = server.join().unwrap();
+        assert!(request.contains(&format!("Host: 127.0.0.1:{}\r\n", port)));
+        assert!(request.contains("Content-Length: 4\r\n\r\nping"));
+    }
+}
@@ -0,0 +1,350 @@
+//! mempool.space fetcher for proof inputs
+//!
+//! Works against mempool.space or a self-hosted instance, retrying with
+//! exponential backoff when the API answers 429 Too Many Requests.
+
+use crate::http::{self, HttpUrl};
+use crate::{
+    block_header_merkle_root_and_block_hash, from_esplora_merkle_proof, prepare_proof_input,
+    ProofInput,
+};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Public mempool.space API
+pub const MEMPOOL_SPACE_URL: &str = "https://mempool.space/api";
+
+/// Timeout applied to each HTTP request
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Retries after a 429 before giving up
+const MAX_RETRIES: u32 = 5;
+
+/// Wait before the first retry; doubled on every further 429
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// An HTTP GET transport: returns the status code and body
+pub trait HttpGet {
+    fn get(&self, url: &str) -> Result<(u16, String), String>;
+}
+
+/// HTTPS-capable transport over a blocking reqwest client, for the public API
+pub struct TlsHttp {
+    client: reqwest::blocking::Client,
+}
+
+impl TlsHttp {
+    pub fn new() -> Result<Self, String> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .map_err(|e| format!("http client: {}", e))?;
+        Ok(Self { client })
+    }
+}
+
+impl HttpGet for TlsHttp {
+    fn get(&self, url: &str) -> Result<(u16, String), String> {
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .map_err(|e| format!("GET {}: {}", url, e))?;
+        let status = response.status().as_u16();
+        let body = response
+            .text()
+            .map_err(|e| format!("read {}: {}", url, e))?;
+        Ok((status, body))
+    }
+}
+
+/// Plain HTTP transport without TLS (`http://` only, e.g. a self-hosted instance on
+/// the LAN)
+pub struct PlainHttp;
+
+impl HttpGet for PlainHttp {
+    fn get(&self, url: &str) -> Result<(u16, String), String> {
+        http::send(&HttpUrl::parse(url, 80)?, "GET", &[], "", HTTP_TIMEOUT)
+    }
+}
+
+/// mempool.space REST client over any `HttpGet` transport
+pub struct MempoolClient<T: HttpGet> {
+    base_url: String,
+    http: T,
+    initial_backoff: Duration,
+}
+
+impl<T: HttpGet> MempoolClient<T> {
+    pub fn new(base_url: &str, http: T) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http,
+            initial_backoff: INITIAL_BACKOFF,
+        }
+    }
+
+    /// Override the wait before the first retry after a 429
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Fetch raw tx, merkle siblings, position and header for a confirmed txid
+    pub fn fetch(&self, txid: &str) -> Result<ProofInput, String> {
+        let tx_hex = self.get_text(&format!("/tx/{}/hex", txid))?;
+
+        let status = self.get_json(&format!("/tx/{}/status", txid))?;
+        let block_hash = match (
+            status.get("confirmed").and_then(Value::as_bool),
+            status.get("block_hash").and_then(Value::as_str),
+        ) {
+            (Some(true), Some(hash)) => hash.to_string(),
+            _ => return Err(format!("tx {} is unconfirmed", txid)),
+        };
+
+        let proof = self.get_json(&format!("/tx/{}/merkle-proof", txid))?;
+        let (merkle_siblings, pos) = from_esplora_merkle_proof(&proof)?;
+
+        let block_header = self.get_text(&format!("/block/{}/header", block_hash))?;
+        let (_, header_hash) = block_header_merkle_root_and_block_hash(&block_header)?;
+        if !header_hash.eq_ignore_ascii_case(&block_hash) {
+            return Err("block header does not match the tx's block".into());
+        }
+
+        prepare_proof_input(&tx_hex, txid, &merkle_siblings, pos, &block_header)
+    }
+
+    /// GET a path, backing off exponentially while rate limited
+    fn get(&self, path: &str) -> Result<String, String> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut backoff = self.initial_backoff;
+        for attempt in 0..=MAX_RETRIES {
+            let (status, body) = self.http.get(&url)?;
+            match status {
+                200..=299 => return Ok(body),
+                429 if attempt < MAX_RETRIES => {
+                    std::thread::sleep(backoff);
+                    backoff = backoff.saturating_mul(2);
+                }
+                429 => break,
+                404 => return Err(format!("not found: {}", url)),
+                _ => return Err(format!("{} returned {}: {}", url, status, body.trim())),
+            }
+        }
+        Err(format!(
+            "rate limited: {} still answered 429 after {} retries",
+            url, MAX_RETRIES
+        ))
+    }
+
+    fn get_text(&self, path: &str) -> Result<String, String> {
+        Ok(self.get(path)?.trim().to_string())
+    }
+
+    fn get_json(&self, path: &str) -> Result<Value, String> {
+        let body = self.get(path)?;
+        serde_json::from_str(&body).map_err(|e| format!("invalid json from {}: {}", path, e))
+    }
+}
+
+/// Fetch proof inputs for `txid` from the mempool.space-compatible API at `base_url`
+/// (`MEMPOOL_SPACE_URL` or a self-hosted instance), over HTTPS or plain HTTP
+pub fn fetch(base_url: &str, txid: &str) -> Result<ProofInput, String> {
+    MempoolClient::new(base_url, TlsHttp::new()?).fetch(txid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{BLOCK_HASH, BLOCK_HEADER, MERKLE_SIBLINGS, TXID, TX_HEX};
+    use crate::verify_tx_in_block_and_outputs;
+    use serde_json::json;
+    use std::cell::RefCell;
+    use std::collections::{HashMap, VecDeque};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Replays recorded responses per URL, in order; the last one repeats
+    struct RecordedHttp {
+        responses: RefCell<HashMap<String, VecDeque<(u16, String)>>>,
+        calls: RefCell<Vec<String>>,
+    }
+
+    impl RecordedHttp {
+        fn new(base_url: &str) -> Self {
+            let mut responses = HashMap::new();
+            let mut record = |path: String, body: String| {
+                responses.insert(
+                    format!("{}{}", base_url, path),
+                    VecDeque::from([(200, body)]),
+                );
+            };
+            record(format!("/tx/{}/hex", TXID), TX_HEX.to_string());
+            record(
+                format!("/tx/{}/status", TXID),
+                json!({
+                    "confirmed": true,
+                    "block_height": 363348,
+                    "block_hash": BLOCK_HASH,
+                    "block_time": 1435754650
+                })
+                .to_string(),
+            );
+            record(
+                format!("/tx/{}/merkle-proof", TXID),
+                json!({ "block_height": 363348, "merkle": MERKLE_SIBLINGS, "pos": 1465 })
+                    .to_string(),
+            );
+            record(
+                format!("/block/{}/header", BLOCK_HASH),
+                BLOCK_HEADER.to_string(),
+            );
+            Self {
+                responses: RefCell::new(responses),
+                calls: RefCell::new(Vec::new()),
+            }
+        }
+
+        /// Answer `times` 429s for `path` before its recorded response
+        fn rate_limit(&self, base_url: &str, path: &str, times: usize) {
+            let mut responses = self.responses.borrow_mut();
+            let queue = responses.get_mut(&format!("{}{}", base_url, path)).unwrap();
+            for _ in 0..times {
+                queue.push_front((429, "Too Many Requests".to_string()));
+            }
+        }
+    }
+
+    impl HttpGet for RecordedHttp {
+        fn get(&self, url: &str) -> Result<(u16, String), String> {
+            self.calls.borrow_mut().push(url.to_string());
+            let mut responses = self.responses.borrow_mut();
+            match responses.get_mut(url) {
+                Some(queue) if queue.len() > 1 => Ok(queue.pop_front().unwrap()),
+                Some(queue) => Ok(queue[0].clone()),
+                None => Ok((404, "Transaction not found".to_string())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fetch_from_recorded_mempool() {
+        let base_url = "https://mempool.example/api";
+        let client = MempoolClient::new(base_url, RecordedHttp::new(base_url));
+
+        let input = client.fetch(TXID).unwrap();
+        assert_eq!(input.tx_hex, TX_HEX);
+        assert_eq!(input.txid, TXID);
+        assert_eq!(input.merkle_siblings, MERKLE_SIBLINGS);
+        assert_eq!(input.pos, 1465);
+        assert_eq!(input.block_header, BLOCK_HEADER);
+
+        let result = verify_tx_in_block_and_outputs(
+            &input.tx_hex,
+            &input.txid,
+            input.merkle_siblings,
+            input.pos,
+            &input.block_header,
+            "1BUBQuPV3gEV7P2XLNuAJQjf5t265Yyj9t",
+            None,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(result.block_hash, BLOCK_HASH);
+
+        // Unknown txids are not found
+        let err = client.fetch(&"00".repeat(32)).unwrap_err();
+        assert!(err.contains("not found"), "unexpected error: {}", err);
+    }
+
+    #[test]
+    fn test_fetch_retries_after_429() {
+        let base_url = "http://mempool.local/api";
+        let hex_path = format!("/tx/{}/hex", TXID);
+
+        // 429 then 200: the fetch succeeds after one retry
+        let http = RecordedHttp::new(base_url);
+        http.rate_limit(base_url, &hex_path, 1);
+        let client = MempoolClient::new(base_url, http).with_initial_backoff(Duration::ZERO);
+        assert_eq!(client.fetch(TXID).unwrap().pos, 1465);
+        let hex_url = format!("{}{}", base_url, hex_path);
+        let hex_calls = client
+            .http
+            .calls
+            .borrow()
+            .iter()
+            .filter(|url| **url == hex_url)
+            .count();
+        assert_eq!(hex_calls, 2);
+
+        // Rate limited on every attempt: give up after the retry budget
+        let http = RecordedHttp::new(base_url);
+        http.rate_limit(base_url, &hex_path, MAX_RETRIES as usize + 1);
+        let client = MempoolClient::new(base_url, http).with_initial_backoff(Duration::ZERO);
+        let err = client.fetch(TXID).unwrap_err();
+        assert!(err.contains("rate limited"), "unexpected error: {}", err);
+        assert_eq!(client.http.calls.borrow().len(), MAX_RETRIES as usize + 1);
+    }
+
+    /// Serve the recorded responses over HTTP on localhost, one request per connection
+    fn spawn_recorded_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let origin = format!("http://{}", listener.local_addr().unwrap());
+        let base_url = format!("{}/api", origin);
+        // Keyed by request path
+        let responses: HashMap<String, (u16, String)> = RecordedHttp::new(&base_url)
+            .responses
+            .into_inner()
+            .into_iter()
+            .map(|(url, queue)| (url[origin.len()..].to_string(), queue[0].clone()))
+            .collect();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let request = String::from_utf8(request).unwrap();
+                let path = request.split_whitespace().nth(1).unwrap();
+                let (status, body) = responses
+                    .get(path)
+                    .cloned()
+                    .unwrap_or((404, "Transaction not found".to_string()));
+                write!(
+                    stream,
+                    "HTTP/1.1 {} OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        base_url
+    }
+
+    #[test]
+    fn test_fetch_over_http_transports() {
+        let base_url = spawn_recorded_server();
+
+        // The default transport, which also speaks TLS to the public API
+        let input = fetch(&base_url, TXID).unwrap();
+        assert_eq!(input.tx_hex, TX_HEX);
+        assert_eq!(input.pos, 1465);
+        assert_eq!(input.block_header, BLOCK_HEADER);
+        let err = fetch(&base_url, &"00".repeat(32)).unwrap_err();
+        assert!(err.contains("not found"), "unexpected error: {}", err);
+
+        // The plain transport reaches the same server, but not an https:// url
+        let input = MempoolClient::new(&base_url, PlainHttp)
+            .fetch(TXID)
+            .unwrap();
+        assert_eq!(input.merkle_siblings, MERKLE_SIBLINGS);
+        assert!(PlainHttp.get("https://mempool.space/api/blocks").is_err());
+    }
+}
@@ -0,0 +1,290 @@
+//! bitcoind JSON-RPC fetcher for proof inputs
+//!
+//! Lets node operators build proof inputs from their own node instead of
+//! trusting a third-party explorer.
+
+use crate::http::{self, HttpUrl};
+use crate::{decode_hex, parse_partial_merkle_tree, prepare_proof_input, sha256d, ProofInput};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Timeout applied to connect, read and write on the RPC socket
+const RPC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Credentials for bitcoind's HTTP basic auth (`rpcuser`/`rpcpassword`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcAuth {
+    pub user: String,
+    pub password: String,
+}
+
+/// A JSON-RPC transport: sends one call and returns its `result`
+pub trait RpcTransport {
+    fn call(&self, method: &str, params: Value) -> Result<Value, String>;
+}
+
+/// Plain HTTP transport for a bitcoind RPC endpoint (`http://host:port[/path]`)
+pub struct HttpTransport {
+    url: HttpUrl,
+    auth: Option<RpcAuth>,
+}
+
+impl HttpTransport {
+    pub fn new(rpc_url: &str, auth: Option<RpcAuth>) -> Result<Self, String> {
+        let url = HttpUrl::parse(rpc_url, 8332).map_err(|e| format!("invalid rpc url: {}", e))?;
+        Ok(Self { url, auth })
+    }
+
+    fn post(&self, body: &str) -> Result<(u16, String), String> {
+        let mut headers = vec![
+            "Content-Type: application/json".to_string(),
+            format!("Content-Length: {}", body.len()),
+        ];
+        if let Some(auth) = &self.auth {
+            let credentials = format!("{}:{}", auth.user, auth.password);
+            headers.push(format!(
+                "Authorization: Basic {}",
+                base64_encode(credentials.as_bytes())
+            ));
+        }
+        http::send(&self.url, "POST", &headers, body, RPC_TIMEOUT).map_err(|e| format!("rpc {}", e))
+    }
+}
+
+impl RpcTransport for HttpTransport {
+    fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+        let body = json!({
+            "jsonrpc": "1.0",
+            "id": "zk-bitcoin-light-client",
+            "method": method,
+            "params": params,
+        })
+        .to_string();
+
+        let (status, body) = self.post(&body)?;
+        if status == 401 || status == 403 {
+            return Err(format!(
+                "{}: rpc authentication failed ({})",
+                method, status
+            ));
+        }
+        // bitcoind reports RPC errors as a JSON body on a non-200 status
+        let response: Value = serde_json::from_str(&body)
+            .map_err(|e| format!("{}: invalid rpc response (http {}): {}", method, status, e))?;
+        if let Some(error) = response.get("error").filter(|e| !e.is_null()) {
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error");
+            return Err(format!("{}: {}", method, message));
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| format!("{}: rpc response has no result", method))
+    }
+}
+
+/// Standard base64 with padding, for the basic auth header
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Fetch raw tx, merkle siblings, position and header for a confirmed txid from bitcoind
+pub fn fetch_proof_inputs(
+    rpc_url: &str,
+    auth: Option<RpcAuth>,
+    txid: &str,
+) -> Result<ProofInput, String> {
+    let transport = HttpTransport::new(rpc_url, auth)?;
+    fetch_proof_inputs_with(&transport, txid)
+}
+
+/// Same as `fetch_proof_inputs`, over any transport
+pub fn fetch_proof_inputs_with<T: RpcTransport>(
+    transport: &T,
+    txid: &str,
+) -> Result<ProofInput, String> {
+    let tx = transport.call("getrawtransaction", json!([txid, true]))?;
+    let tx_hex = tx
+        .get("hex")
+        .and_then(Value::as_str)
+        .ok_or("getrawtransaction: missing hex")?
+        .to_string();
+    let block_hash = tx
+        .get("blockhash")
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("tx {} is unconfirmed", txid))?;
+
+    let proof = transport.call("gettxoutproof", json!([[txid], block_hash]))?;
+    let proof_hex = proof
+        .as_str()
+        .ok_or("gettxoutproof: expected a hex string")?;
+    let proof_bytes = decode_hex(proof_hex)?;
+    let (block_header, merkle_siblings, pos) = parse_txout_proof(&proof_bytes)?;
+
+    let mut block_hash_internal = sha256d(&hex::decode(&block_header).unwrap());
+    block_hash_internal.reverse();
+    if !hex::encode(block_hash_internal).eq_ignore_ascii_case(block_hash) {
+        return Err("gettxoutproof: header does not match the tx's block".into());
+    }
+
+    prepare_proof_input(&tx_hex, txid, &merkle_siblings, pos, &block_header)
+}
+
+/// Split a `gettxoutproof` payload (header | partial merkle tree) into header hex,
+/// sibling list (explorer display hex) and position of the single matched tx
+fn parse_txout_proof(bytes: &[u8]) -> Result<(String, Vec<String>, usize), String> {
+    if bytes.len() < 80 {
+        return Err("merkle block too short".into());
+    }
+    let header = &bytes[0..80];
+    let (siblings, pos, root) = parse_partial_merkle_tree(&bytes[80..])?;
+    if root[..] != header[36..68] {
+        return Err("merkle block root does not match header".into());
+    }
+    let siblings = siblings
+        .into_iter()
+        .map(|mut h| {
+            h.reverse();
+            hex::encode(h)
+        })
+        .collect();
+
+    Ok((hex::encode(header), siblings, pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify_tx_in_block_and_outputs;
+    use std::collections::HashMap;
+
+    // Mainnet block 170: the coinbase, then the first transaction between two people
+    // (10 BTC to 1Q2TWHE3GMdB6BZKafqwxXtWAWgFt5Jvm3), at position 1
+    const TXID: &str = "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16";
+    const TX_HEX: &str = "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000";
+    const HEADER_HEX: &str = "0100000055bd840a78798ad0da853f68974f3d183e2bd1db6a842c1feecf222a00000000ff104ccb05421ab93e63f8c3ce5c2c2e9dbb37de2764b3a3175c8166562cac7d51b96a49ffff001d283e9e70";
+    const BLOCK_HASH: &str = "00000000d1145790a8694403d4063f323d499e655c83426834d4ce2f8dd4a2ee";
+    const BLOCK_TIME: u32 = 1231731025;
+    const TXOUT_PROOF: &str = "0100000055bd840a78798ad0da853f68974f3d183e2bd1db6a842c1feecf222a00000000ff104ccb05421ab93e63f8c3ce5c2c2e9dbb37de2764b3a3175c8166562cac7d51b96a49ffff001d283e9e70020000000282501c1178fa0b222c1f3d474ec726b832013f0a532b44bb620cce8624a5feb1169e1e83e930853391bc6f35f605c6754cfead57cf8387639d3b4096c54f18f40105";
+
+    /// Replays recorded responses keyed by RPC method
+    struct MockTransport {
+        responses: HashMap<&'static str, Value>,
+    }
+
+    impl RpcTransport for MockTransport {
+        fn call(&self, method: &str, _params: Value) -> Result<Value, String> {
+            self.responses
+                .get(method)
+                .cloned()
+                .ok_or_else(|| format!("{}: No such mempool or blockchain transaction", method))
+        }
+    }
+
+    fn recorded_node() -> MockTransport {
+        let mut responses = HashMap::new();
+        responses.insert(
+            "getrawtransaction",
+            json!({
+                "txid": TXID,
+                "hash": TXID,
+                "version": 1,
+                "size": 275,
+                "vsize": 275,
+                "weight": 1100,
+                "locktime": 0,
+                "hex": TX_HEX,
+                "blockhash": BLOCK_HASH,
+                "time": BLOCK_TIME,
+                "blocktime": BLOCK_TIME,
+            }),
+        );
+        responses.insert("gettxoutproof", json!(TXOUT_PROOF));
+        MockTransport { responses }
+    }
+
+    #[test]
+    fn test_fetch_proof_inputs_from_recorded_rpc() {
+        let input = fetch_proof_inputs_with(&recorded_node(), TXID).unwrap();
+        assert_eq!(input.tx_hex, TX_HEX);
+        assert_eq!(input.txid, TXID);
+        assert_eq!(input.pos, 1);
+        assert_eq!(input.block_header, HEADER_HEX);
+        // The block's coinbase
+        assert_eq!(
+            input.merkle_siblings,
+            vec!["b1fea52486ce0c62bb442b530a3f0132b826c74e473d1f2c220bfa78111c5082"]
+        );
+
+        // The derived inputs satisfy the verifier
+        let result = verify_tx_in_block_and_outputs(
+            &input.tx_hex,
+            &input.txid,
+            input.merkle_siblings,
+            input.pos,
+            &input.block_header,
+            "1Q2TWHE3GMdB6BZKafqwxXtWAWgFt5Jvm3",
+            None,
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(result.block_hash, BLOCK_HASH);
+        assert_eq!(result.total_amount, 1_000_000_000);
+
+        // Unconfirmed transactions have no blockhash
+        let mut mempool = recorded_node();
+        mempool
+            .responses
+            .insert("getrawtransaction", json!({ "txid": TXID, "hex": TX_HEX }));
+        assert!(fetch_proof_inputs_with(&mempool, TXID)
+            .unwrap_err()
+            .contains("unconfirmed"));
+    }
+
+    #[test]
+    fn test_parse_txout_proof_rejects_tampering() {
+        let mut proof = hex::decode(TXOUT_PROOF).unwrap();
+        // Flip a byte in the first hash so the root no longer matches
+        proof[86] ^= 1;
+        assert!(parse_txout_proof(&proof).is_err());
+
+        // Trailing garbage
+        let mut proof = hex::decode(TXOUT_PROOF).unwrap();
+        proof.push(0);
+        assert!(parse_txout_proof(&proof).is_err());
+    }
+
+    #[test]
+    fn test_http_transport_url_and_auth() {
+        assert!(HttpTransport::new("https://node:8332", None).is_err());
+        let transport = HttpTransport::new("http://127.0.0.1:18443/wallet/w1", None).unwrap();
+        assert_eq!(transport.url.port, 18443);
+        assert_eq!(transport.url.path, "/wallet/w1");
+        assert_eq!(
+            HttpTransport::new("http://node", None).unwrap().url.port,
+            8332
+        );
+        assert_eq!(base64_encode(b"user:pass"), "dXNlcjpwYXNz");
+        assert_eq!(base64_encode(b"ab"), "YWI=");
+    }
+}
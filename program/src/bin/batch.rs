@@ -0,0 +1,30 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use alloy_sol_types::SolType;
+use fibonacci_lib::{verify_batch, BatchProofInput, BatchPublicValuesStruct};
+
+pub fn main() {
+    // Read inputs from SP1 stdin
+    let input = sp1_zkvm::io::read::<BatchProofInput>();
+    let target_address = sp1_zkvm::io::read::<String>();
+
+    // Verify every claim against the shared header; failed claims are recorded, not fatal
+    let batch = verify_batch(&input, &target_address).expect("Batch verification failed");
+
+    // Commit the block, the target, the aggregate and per-claim results, and the total
+    // paid by the valid claims, ABI-encoded
+    let public_values = BatchPublicValuesStruct {
+        blockHash: batch.block_hash,
+        targetAddress: target_address,
+        allValid: batch.all_valid,
+        txids: input
+            .claims
+            .iter()
+            .map(|claim| claim.txid_bytes().into())
+            .collect(),
+        results: batch.results,
+        totalAmount: batch.total_amount,
+    };
+    sp1_zkvm::io::commit_slice(&BatchPublicValuesStruct::abi_encode(&public_values));
+}
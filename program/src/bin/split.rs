@@ -0,0 +1,45 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use alloy_sol_types::SolType;
+use fibonacci_lib::{
+    compute_raw_tx_hash_from_txhex, decode_hex, parse_tx_outputs, verify_inclusion_in_header,
+    verify_targets_with_minimums, verify_txid_bytes, SplitPublicValuesStruct,
+};
+
+pub fn main() {
+    // Read inputs from SP1 stdin; each target is paired with the minimum it must receive
+    let tx_hex = sp1_zkvm::io::read::<String>();
+    let expected_txid = sp1_zkvm::io::read::<String>();
+    let merkle_siblings = sp1_zkvm::io::read::<Vec<String>>();
+    let pos = sp1_zkvm::io::read::<usize>();
+    let block_header = sp1_zkvm::io::read::<String>();
+    let targets = sp1_zkvm::io::read::<Vec<(String, u64)>>();
+
+    // The transaction must hash to the expected txid and be included in the block
+    let tx_bytes = decode_hex(&tx_hex).expect("Invalid transaction hex");
+    assert!(
+        verify_txid_bytes(&expected_txid, &tx_bytes).expect("txid check failed"),
+        "txid mismatch"
+    );
+    let block_hash =
+        verify_inclusion_in_header(&expected_txid, &merkle_siblings, pos, &block_header)
+            .expect("Merkle inclusion failed");
+
+    // Every target must be paid at least its minimum
+    let outputs = parse_tx_outputs(&tx_hex).expect("Output parsing failed");
+    let totals = verify_targets_with_minimums(outputs, &targets).expect("Target minimum not met");
+
+    // Commit the block, the txid (internal byte order), the targets with their minimums
+    // and the total paid to each, in the order the targets were given
+    let txid = compute_raw_tx_hash_from_txhex(&tx_hex).expect("txid computation failed");
+    let (targets, minimums) = targets.into_iter().unzip();
+    let public_values = SplitPublicValuesStruct {
+        blockHash: block_hash,
+        txid: txid.into(),
+        targets,
+        minimums,
+        totals,
+    };
+    sp1_zkvm::io::commit_slice(&SplitPublicValuesStruct::abi_encode(&public_values));
+}
@@ -0,0 +1,51 @@
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use alloy_sol_types::SolType;
+use fibonacci_lib::{
+    compute_raw_tx_hash_from_txhex, parse_block_header, verify_spv_proof, Network,
+    SpvPublicValuesStruct,
+};
+
+pub fn main() {
+    // Read inputs from SP1 stdin; headers start at the transaction's block, in chain order
+    let headers = sp1_zkvm::io::read::<Vec<String>>();
+    let tx_hex = sp1_zkvm::io::read::<String>();
+    let merkle_siblings = sp1_zkvm::io::read::<Vec<String>>();
+    let pos = sp1_zkvm::io::read::<usize>();
+    let target_address = sp1_zkvm::io::read::<String>();
+    let min_confirmations = sp1_zkvm::io::read::<u32>();
+    let network = sp1_zkvm::io::read::<Network>();
+
+    // Verify the header chain, inclusion in its first block and the confirmation depth
+    let result = verify_spv_proof(
+        &headers,
+        &tx_hex,
+        &merkle_siblings,
+        pos,
+        &target_address,
+        min_confirmations,
+        network,
+    )
+    .expect("SPV verification failed");
+
+    // Commit the block, the amount paid to the target, the txid (internal byte order) and
+    // how many confirmations the header chain proves. The tip hash and network let a
+    // verifier check the chain against its own view: a chain ending off the best chain,
+    // or built on testnet's min-difficulty rule, proves nothing about mainnet
+    let txid = compute_raw_tx_hash_from_txhex(&tx_hex).expect("txid computation failed");
+    let confirmations = u32::try_from(headers.len()).expect("too many headers");
+    let tip_hash = parse_block_header(headers.last().expect("no headers"))
+        .and_then(|header| header.block_hash())
+        .expect("tip hash computation failed");
+    let public_values = SpvPublicValuesStruct {
+        blockHash: result.block_hash,
+        totalAmount: result.total_amount,
+        txid: txid.into(),
+        confirmations,
+        targetAddress: target_address,
+        network: network as u8,
+        tipHash: tip_hash,
+    };
+    sp1_zkvm::io::commit_slice(&SpvPublicValuesStruct::abi_encode(&public_values));
+}
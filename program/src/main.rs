@@ -1,7 +1,12 @@
 #![no_main]
 sp1_zkvm::entrypoint!(main);
 
-use fibonacci_lib::verify_tx_in_block_and_outputs;
+use alloy_sol_types::SolType;
+use fibonacci_lib::{
+    compute_raw_tx_hash_from_txhex, target_outpoint, total_op_return_bytes,
+    verify_inputs_confirmed, verify_op_return_commitment, verify_tx_in_block_and_outputs,
+    FundingProof, PublicValuesStruct,
+};
 
 pub fn main() {
     // Read inputs from SP1 stdin
@@ -11,6 +16,12 @@ pub fn main() {
     let pos = sp1_zkvm::io::read::<usize>();
     let block_header = sp1_zkvm::io::read::<String>();
     let target_address = sp1_zkvm::io::read::<String>();
+    let commit_op_return_bytes = sp1_zkvm::io::read::<bool>();
+    let funding_proofs = sp1_zkvm::io::read::<Vec<FundingProof>>();
+    let expected_block_hash = sp1_zkvm::io::read::<Option<String>>();
+    let reject_coinbase = sp1_zkvm::io::read::<bool>();
+    let allow_no_match = sp1_zkvm::io::read::<bool>();
+    let expected_op_return = sp1_zkvm::io::read::<Option<[u8; 32]>>();
 
     // Verify transaction in block and sum outputs to target address
     let result = verify_tx_in_block_and_outputs(
@@ -20,12 +31,62 @@ pub fn main() {
         pos,
         &block_header,
         &target_address,
+        expected_block_hash.as_deref(),
+        reject_coinbase,
+        allow_no_match,
     );
 
     // Verification must pass
-    let (block_hash, total_amount) = result.expect("Transaction verification failed");
+    let result = result.expect("Transaction verification failed");
 
-    // Commit the results to SP1 output
-    sp1_zkvm::io::commit(&block_hash);
-    sp1_zkvm::io::commit(&total_amount);
+    // Commit the outpoint of the first output paying the target, so consumers can track the UTXO
+    // A proof of no payment (allow_no_match, zero total) commits output index u32::MAX
+    let (txid, output_index): ([u8; 32], u32) = if result.matched_outputs == 0 {
+        let txid = compute_raw_tx_hash_from_txhex(&tx_hex).expect("txid computation failed");
+        (txid, u32::MAX)
+    } else {
+        target_outpoint(&tx_hex, &target_address).expect("Target outpoint lookup failed")
+    };
+
+    // How many bytes the transaction anchored in OP_RETURN outputs
+    let op_return_bytes = match commit_op_return_bytes {
+        true => total_op_return_bytes(&tx_hex).expect("OP_RETURN parsing failed"),
+        false => 0,
+    };
+
+    // Chained-proof mode: whether every input spends a proven-confirmed funding tx,
+    // and the blocks the funding txs were confirmed in
+    let inputs = verify_inputs_confirmed(&tx_hex, &funding_proofs).expect("Funding proof invalid");
+    let all_inputs_confirmed = !funding_proofs.is_empty() && inputs.all_confirmed;
+
+    // Anchor mode: whether an OP_RETURN output carries exactly the expected value
+    let op_return_anchored = match expected_op_return {
+        Some(expected) => {
+            verify_op_return_commitment(&tx_hex, expected).expect("OP_RETURN parsing failed")
+        }
+        None => false,
+    };
+
+    // Commit every value and mode flag ABI-encoded, in one fixed layout
+    // (checkpoint mode needs no value: verification already checked the header's hash)
+    let public_values = PublicValuesStruct {
+        blockHash: result.block_hash,
+        totalAmount: result.total_amount,
+        txid: txid.into(),
+        outputIndex: output_index,
+        isCoinbase: result.is_coinbase,
+        targetAddress: target_address,
+        opReturnBytesCommitted: commit_op_return_bytes,
+        opReturnBytes: op_return_bytes,
+        inputsChecked: !funding_proofs.is_empty(),
+        allInputsConfirmed: all_inputs_confirmed,
+        fundingBlockHashes: inputs.funding_block_hashes,
+        blockHashPinned: expected_block_hash.is_some(),
+        coinbaseRejected: reject_coinbase,
+        noMatchAllowed: allow_no_match,
+        opReturnChecked: expected_op_return.is_some(),
+        expectedOpReturn: expected_op_return.unwrap_or_default().into(),
+        opReturnAnchored: op_return_anchored,
+    };
+    sp1_zkvm::io::commit_slice(&PublicValuesStruct::abi_encode(&public_values));
 }
@@ -10,7 +10,9 @@
 //! RUST_LOG=info cargo run --release --bin evm -- --system plonk
 //! ```
 
-use clap::{Parser, ValueEnum};
+use alloy_sol_types::SolType;
+use clap::Parser;
+use fibonacci_lib::{FundingProof, ProofSystem, PublicValuesStruct};
 use serde::{Deserialize, Serialize};
 use sp1_sdk::{
     include_elf, HashableKey, ProverClient, SP1ProofWithPublicValues, SP1Stdin, SP1VerifyingKey,
@@ -24,17 +26,10 @@ pub const BITCOIN_VERIFICATION_ELF: &[u8] = include_elf!("fibonacci-program");
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct EVMArgs {
-    #[arg(long, value_enum, default_value = "groth16")]
+    #[arg(long, default_value = "groth16")]
     system: ProofSystem,
 }
 
-/// Enum representing the available proof systems
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
-enum ProofSystem {
-    Plonk,
-    Groth16,
-}
-
 /// A fixture that can be used to test the verification of SP1 zkVM proofs inside Solidity.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -52,6 +47,10 @@ fn main() {
 
     // Parse the command line arguments.
     let args = EVMArgs::parse();
+    assert!(
+        args.system != ProofSystem::Compressed,
+        "EVM fixtures need --system plonk or groth16"
+    );
 
     // Setup the prover client.
     let client = ProverClient::from_env();
@@ -88,6 +87,14 @@ fn main() {
     stdin.write(&pos);
     stdin.write(&block_header);
     stdin.write(&target_address);
+    // No OP_RETURN byte commitment, no funding proofs, no pinned block, coinbase allowed,
+    // at least one output must pay the target, no OP_RETURN anchor check
+    stdin.write(&false);
+    stdin.write(&Vec::<FundingProof>::new());
+    stdin.write(&None::<String>);
+    stdin.write(&false);
+    stdin.write(&false);
+    stdin.write(&None::<[u8; 32]>);
 
     println!("Proof System: {:?}", args.system);
 
@@ -95,6 +102,7 @@ fn main() {
     let proof = match args.system {
         ProofSystem::Plonk => client.prove(&pk, &stdin).plonk().run(),
         ProofSystem::Groth16 => client.prove(&pk, &stdin).groth16().run(),
+        ProofSystem::Compressed => unreachable!("rejected above"),
     }
     .expect("failed to generate proof");
 
@@ -107,10 +115,13 @@ fn create_proof_fixture(
     vk: &SP1VerifyingKey,
     system: ProofSystem,
 ) {
-    // For now, we'll use placeholder values since the public values structure needs to be defined
-    // In a real implementation, you would decode the public values from the proof
-    let block_hash = "placeholder_block_hash".to_string();
-    let total_amount = 1240000000u64; // Expected amount from our test
+    // Decode the ABI-encoded public values the program committed.
+    let PublicValuesStruct {
+        blockHash: block_hash,
+        totalAmount: total_amount,
+        ..
+    } = PublicValuesStruct::abi_decode(proof.public_values.as_slice())
+        .expect("failed to decode public values");
 
     // Create the testing fixture so we can test things end-to-end.
     let fixture = SP1BitcoinProofFixture {
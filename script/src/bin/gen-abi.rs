@@ -0,0 +1,14 @@
+//! Print the Solidity definition and ABI signature of `PublicValuesStruct`, so the
+//! contract's copy can be regenerated instead of kept in sync by hand.
+//!
+//! You can run this script using the following command:
+//! ```shell
+//! cargo run --release --bin gen-abi
+//! ```
+
+use fibonacci_lib::{public_values_abi_signature, public_values_solidity_struct};
+
+fn main() {
+    println!("// ABI signature: {}", public_values_abi_signature());
+    println!("{}", public_values_solidity_struct());
+}